@@ -0,0 +1,341 @@
+use thiserror::Error;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::types::{body::BodyStream, header_map::HeaderMap};
+
+const CRLF: &[u8] = b"\r\n";
+const CHUNK_EXTENSION_SEPARATOR: char = ';';
+// Size lines only grow beyond a few bytes with chunk extensions, which are ignored anyway
+const MAX_LINE_LENGTH: usize = 4 * 1024;
+const MAX_TRAILER_SECTION_SIZE: usize = 16 * 1024;
+
+#[derive(Error, Debug)]
+pub enum ParseError {
+    #[error("Failed to read chunk: {0:?}")]
+    ReadError(#[from] std::io::Error),
+
+    #[error("Invalid chunk size: {0}")]
+    InvalidChunkSize(String),
+
+    #[error("Chunk is not terminated with CRLF")]
+    MissingChunkTerminator,
+
+    #[error("Chunk line is not terminated with CRLF")]
+    InvalidLineEnding,
+
+    #[error("Invalid trailer field: {0}")]
+    InvalidTrailer(String),
+
+    #[error("Unexpected end of chunked body")]
+    UnexpectedEof,
+
+    #[error("Chunked body exceeds the limit of {0} bytes")]
+    BodyTooLarge(u64),
+
+    #[error("Chunk line exceeds the limit of {0} bytes")]
+    LineTooLong(usize),
+}
+
+#[derive(Debug, Default)]
+pub struct ChunkedBody {
+    pub data: Vec<u8>,
+    pub trailers: HeaderMap,
+}
+
+async fn read_line<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    limit: usize,
+) -> Result<String, ParseError> {
+    let mut line = Vec::new();
+    if reader
+        .take(limit as u64 + 1)
+        .read_until(b'\n', &mut line)
+        .await?
+        == 0
+    {
+        return Err(ParseError::UnexpectedEof);
+    }
+
+    if line.len() > limit {
+        return Err(ParseError::LineTooLong(limit));
+    }
+
+    // A bare LF is not accepted, recipients that disagree on it would frame the body differently
+    let line = match line.strip_suffix(CRLF) {
+        Some(line) => line,
+        None if line.ends_with(b"\n") => return Err(ParseError::InvalidLineEnding),
+        None => return Err(ParseError::UnexpectedEof),
+    };
+
+    Ok(String::from_utf8_lossy(line).into_owned())
+}
+
+fn parse_chunk_size(line: &str) -> Result<usize, ParseError> {
+    // Chunk extensions are allowed by RFC 9112 section 7.1.1, but we don't have any use for them.
+    // Only the whitespace the grammar allows before an extension is skipped.
+    let size = line
+        .split_once(CHUNK_EXTENSION_SEPARATOR)
+        .map_or(line, |(size, _)| size.trim_end_matches([' ', '\t']));
+
+    if size.is_empty() || !size.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return Err(ParseError::InvalidChunkSize(line.to_owned()));
+    }
+
+    usize::from_str_radix(size, 16).map_err(|_| ParseError::InvalidChunkSize(line.to_owned()))
+}
+
+/// Reads the size line of the next chunk, a size of zero marks the last chunk
+pub async fn read_chunk_size<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<usize, ParseError> {
+    parse_chunk_size(&read_line(reader, MAX_LINE_LENGTH).await?)
+}
+
+/// Reads the CRLF that follows the data of every chunk
+pub async fn read_chunk_end<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<(), ParseError> {
+    if !read_line(reader, MAX_LINE_LENGTH).await?.is_empty() {
+        return Err(ParseError::MissingChunkTerminator);
+    }
+
+    Ok(())
+}
+
+/// Reads the trailer section that follows the last chunk, up to the final empty line
+pub async fn read_trailers<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+) -> Result<HeaderMap, ParseError> {
+    let mut trailers = HeaderMap::new();
+    let mut remaining = MAX_TRAILER_SECTION_SIZE;
+
+    loop {
+        let line = read_line(reader, remaining).await?;
+        remaining = remaining.saturating_sub(line.len());
+
+        if line.is_empty() {
+            return Ok(trailers);
+        }
+
+        let Some((key, value)) = line.split_once(':') else {
+            return Err(ParseError::InvalidTrailer(line));
+        };
+
+        trailers.append(key.trim_end(), value.trim());
+    }
+}
+
+/// Decodes a body sent with `Transfer-Encoding: chunked` as described in RFC 9112 section 7.1,
+/// failing before any chunk is read that would grow the body beyond `max_size`
+pub async fn decode<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    max_size: u64,
+) -> Result<ChunkedBody, ParseError> {
+    let mut data = Vec::new();
+
+    loop {
+        let chunk_size = read_chunk_size(reader).await?;
+
+        if chunk_size == 0 {
+            break;
+        }
+
+        if (data.len() as u64).saturating_add(chunk_size as u64) > max_size {
+            return Err(ParseError::BodyTooLarge(max_size));
+        }
+
+        let offset = data.len();
+        data.resize(offset + chunk_size, 0);
+        reader
+            .read_exact(&mut data[offset..])
+            .await
+            .map_err(|_| ParseError::UnexpectedEof)?;
+
+        read_chunk_end(reader).await?;
+    }
+
+    Ok(ChunkedBody {
+        data,
+        trailers: read_trailers(reader).await?,
+    })
+}
+
+/// Writes the stream as a chunked body terminated with a last-chunk and no trailers
+pub async fn encode<W: AsyncWrite + Unpin>(
+    stream: &mut BodyStream,
+    writer: &mut W,
+) -> std::io::Result<()> {
+    while let Some(chunk) = stream.next_chunk().await? {
+        writer
+            .write_all(format!("{:X}\r\n", chunk.len()).as_bytes())
+            .await?;
+        writer.write_all(&chunk).await?;
+        writer.write_all(CRLF).await?;
+    }
+
+    writer.write_all(b"0\r\n\r\n").await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn decode_all(body: &[u8], max_size: u64) -> Result<ChunkedBody, ParseError> {
+        decode(&mut &body[..], max_size).await
+    }
+
+    #[tokio::test]
+    async fn decodes_chunks_and_trailers() {
+        let body = decode_all(
+            b"5\r\nhello\r\n7\r\n, world\r\n0\r\nX-Checksum: abc\r\nX-Note:  spaced \r\n\r\n",
+            1024,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(body.data, b"hello, world");
+        assert_eq!(body.trailers.get("x-checksum"), Some("abc"));
+        assert_eq!(body.trailers.get("X-Note"), Some("spaced"));
+    }
+
+    #[tokio::test]
+    async fn ignores_chunk_extensions() {
+        let body = decode_all(
+            b"5;name=value\r\nhello\r\n6 ; quoted=\"a;b\"\r\n world\r\n0;last\r\n\r\n",
+            1024,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(body.data, b"hello world");
+        assert!(body.trailers.is_empty());
+    }
+
+    #[tokio::test]
+    async fn accepts_hexadecimal_sizes_in_any_case() {
+        let data = vec![b'x'; 0xab];
+        let mut encoded = b"aB\r\n".to_vec();
+        encoded.extend_from_slice(&data);
+        encoded.extend_from_slice(b"\r\n0\r\n\r\n");
+
+        assert_eq!(decode_all(&encoded, 1024).await.unwrap().data, data);
+    }
+
+    #[tokio::test]
+    async fn rejects_chunk_sizes_that_overflow() {
+        assert!(matches!(
+            decode_all(b"10000000000000000\r\n", u64::MAX).await,
+            Err(ParseError::InvalidChunkSize(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn rejects_malformed_chunk_sizes() {
+        for body in [
+            &b"\r\n"[..],
+            b" 5\r\nhello\r\n0\r\n\r\n",
+            b"5 \r\nhello\r\n0\r\n\r\n",
+            b"+5\r\nhello\r\n0\r\n\r\n",
+            b"0x5\r\nhello\r\n0\r\n\r\n",
+            b";ext\r\n",
+        ] {
+            assert!(
+                matches!(
+                    decode_all(body, 1024).await,
+                    Err(ParseError::InvalidChunkSize(_))
+                ),
+                "{body:?}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_bare_line_feeds() {
+        for body in [
+            &b"5\nhello\r\n0\r\n\r\n"[..],
+            b"5\r\nhello\n0\r\n\r\n",
+            b"0\r\nX-Trailer: a\n\r\n",
+            b"0\r\n\n",
+        ] {
+            assert!(
+                matches!(
+                    decode_all(body, 1024).await,
+                    Err(ParseError::InvalidLineEnding)
+                ),
+                "{body:?}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_chunk_data_without_crlf() {
+        assert!(matches!(
+            decode_all(b"5\r\nhelloX\r\n0\r\n\r\n", 1024).await,
+            Err(ParseError::MissingChunkTerminator)
+        ));
+    }
+
+    #[tokio::test]
+    async fn rejects_truncated_bodies() {
+        for body in [&b"5\r\nhel"[..], b"5\r\nhello\r\n", b"0\r\n", b"5"] {
+            assert!(
+                matches!(decode_all(body, 1024).await, Err(ParseError::UnexpectedEof)),
+                "{body:?}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_trailers_without_colon() {
+        assert!(matches!(
+            decode_all(b"0\r\nnot a field\r\n\r\n", 1024).await,
+            Err(ParseError::InvalidTrailer(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn limits_the_body_size() {
+        let body = b"5\r\nhello\r\n6\r\n world\r\n0\r\n\r\n";
+
+        assert_eq!(decode_all(body, 11).await.unwrap().data, b"hello world");
+
+        // The chunk that would cross the limit is refused before it's read
+        assert!(matches!(
+            decode_all(body, 10).await,
+            Err(ParseError::BodyTooLarge(10))
+        ));
+        assert!(matches!(
+            decode_all(b"ffffffff\r\n", 10).await,
+            Err(ParseError::BodyTooLarge(10))
+        ));
+    }
+
+    #[tokio::test]
+    async fn limits_the_line_length() {
+        let mut body = b"5;".to_vec();
+        body.extend(std::iter::repeat_n(b'x', MAX_LINE_LENGTH));
+        body.extend_from_slice(b"\r\nhello\r\n0\r\n\r\n");
+
+        assert!(matches!(
+            decode_all(&body, 1024).await,
+            Err(ParseError::LineTooLong(MAX_LINE_LENGTH))
+        ));
+    }
+
+    #[tokio::test]
+    async fn encodes_every_chunk_and_the_last_chunk() {
+        let (sender, mut stream) = BodyStream::channel(4);
+        for chunk in [&b"hello"[..], b"", &[b'x'; 26]] {
+            sender.send(chunk.to_vec()).await.unwrap();
+        }
+        drop(sender);
+
+        let mut encoded = Vec::new();
+        encode(&mut stream, &mut encoded).await.unwrap();
+
+        let mut expected = b"5\r\nhello\r\n1A\r\n".to_vec();
+        expected.extend_from_slice(&[b'x'; 26]);
+        expected.extend_from_slice(b"\r\n0\r\n\r\n");
+        assert_eq!(encoded, expected);
+
+        // What is encoded decodes to the same data
+        let decoded = decode_all(&encoded, 1024).await.unwrap();
+        assert_eq!(decoded.data.len(), 31);
+    }
+}
@@ -0,0 +1,97 @@
+use std::{str::FromStr, time::Duration};
+
+const KEEP_ALIVE_TIMEOUT_ENV: &str = "KEEP_ALIVE_TIMEOUT";
+const MAX_REQUESTS_PER_CONNECTION_ENV: &str = "MAX_REQUESTS_PER_CONNECTION";
+const MAX_REQUEST_LINE_LENGTH_ENV: &str = "MAX_REQUEST_LINE_LENGTH";
+const MAX_HEADER_COUNT_ENV: &str = "MAX_HEADER_COUNT";
+const MAX_HEADER_SIZE_ENV: &str = "MAX_HEADER_SIZE";
+const MAX_BODY_SIZE_ENV: &str = "MAX_BODY_SIZE";
+const HEADER_READ_TIMEOUT_ENV: &str = "HEADER_READ_TIMEOUT";
+const BODY_READ_TIMEOUT_ENV: &str = "BODY_READ_TIMEOUT";
+const SHUTDOWN_TIMEOUT_ENV: &str = "SHUTDOWN_TIMEOUT";
+
+const DEFAULT_KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(5);
+const DEFAULT_MAX_REQUESTS_PER_CONNECTION: usize = 100;
+const DEFAULT_MAX_REQUEST_LINE_LENGTH: usize = 8 * 1024;
+const DEFAULT_MAX_HEADER_COUNT: usize = 100;
+const DEFAULT_MAX_HEADER_SIZE: usize = 32 * 1024;
+const DEFAULT_MAX_BODY_SIZE: u64 = 10 * 1024 * 1024;
+const DEFAULT_HEADER_READ_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_BODY_READ_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone)]
+pub struct ConnectionConfig {
+    pub keep_alive_timeout: Duration,
+    pub max_requests_per_connection: usize,
+    pub max_request_line_length: usize,
+    pub max_header_count: usize,
+    /// Bytes of all header lines together, excluding the request line
+    pub max_header_size: usize,
+    /// Largest body read into memory, streamed bodies are bounded by their handler instead
+    pub max_body_size: u64,
+    /// Time from the first byte of a request until the end of its header section, also bounds the
+    /// TLS handshake
+    pub header_read_timeout: Duration,
+    /// Time to read a body into memory, streamed bodies may stall for as long between chunks
+    pub body_read_timeout: Duration,
+    /// Time connections are given to finish their requests on shutdown before they are aborted
+    pub shutdown_timeout: Duration,
+}
+
+impl Default for ConnectionConfig {
+    fn default() -> Self {
+        Self {
+            keep_alive_timeout: DEFAULT_KEEP_ALIVE_TIMEOUT,
+            max_requests_per_connection: DEFAULT_MAX_REQUESTS_PER_CONNECTION,
+            max_request_line_length: DEFAULT_MAX_REQUEST_LINE_LENGTH,
+            max_header_count: DEFAULT_MAX_HEADER_COUNT,
+            max_header_size: DEFAULT_MAX_HEADER_SIZE,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            header_read_timeout: DEFAULT_HEADER_READ_TIMEOUT,
+            body_read_timeout: DEFAULT_BODY_READ_TIMEOUT,
+            shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
+        }
+    }
+}
+
+impl ConnectionConfig {
+    /// Defaults overridden by the environment variables of the same name, such as
+    /// `KEEP_ALIVE_TIMEOUT` in seconds or `MAX_BODY_SIZE` in bytes
+    pub fn from_env() -> Self {
+        let default = Self::default();
+
+        Self {
+            keep_alive_timeout: env_secs_or(KEEP_ALIVE_TIMEOUT_ENV, default.keep_alive_timeout),
+            max_requests_per_connection: env_or(
+                MAX_REQUESTS_PER_CONNECTION_ENV,
+                default.max_requests_per_connection,
+            ),
+            max_request_line_length: env_or(
+                MAX_REQUEST_LINE_LENGTH_ENV,
+                default.max_request_line_length,
+            ),
+            max_header_count: env_or(MAX_HEADER_COUNT_ENV, default.max_header_count),
+            max_header_size: env_or(MAX_HEADER_SIZE_ENV, default.max_header_size),
+            max_body_size: env_or(MAX_BODY_SIZE_ENV, default.max_body_size),
+            header_read_timeout: env_secs_or(HEADER_READ_TIMEOUT_ENV, default.header_read_timeout),
+            body_read_timeout: env_secs_or(BODY_READ_TIMEOUT_ENV, default.body_read_timeout),
+            shutdown_timeout: env_secs_or(SHUTDOWN_TIMEOUT_ENV, default.shutdown_timeout),
+        }
+    }
+}
+
+// Values that are missing or fail to parse fall back to the default
+fn env_or<T: FromStr>(name: &str, default: T) -> T {
+    std::env::var(name)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+fn env_secs_or(name: &str, default: Duration) -> Duration {
+    std::env::var(name)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .map_or(default, Duration::from_secs)
+}
@@ -1,46 +1,29 @@
-use crate::types::{
-    body::Body,
-    header::{ContentLength, ContentType},
+use http::types::{
+    body::{Body, BodyStream, BodyType},
+    header::ContentType,
     request::RequestMessage,
-    request_line::{HttpVersion, HttpVersionEnum, RequestType},
     response::ResponseMessage,
-    response_line::ResponseLine,
+    response_header::LastModified,
     status::Status,
 };
 
-pub async fn handle(mut request_message: RequestMessage) -> ResponseMessage {
-    if request_message.request_line.request_type != RequestType::Get {
-        let reponse_line =
-            ResponseLine::new(HttpVersion::new(HttpVersionEnum::V1_1), Status::NOT_FOUND);
+pub async fn handle(_request_message: RequestMessage) -> Result<ResponseMessage, Status> {
+    let file = tokio::fs::File::open("pages/about.html")
+        .await
+        .map_err(|_| Status::INTERNAL_SERVER_ERROR)?;
 
-        return ResponseMessage::new(reponse_line, request_message.header, Body::default());
-    }
-
-    let Ok(contents) = tokio::fs::read_to_string("pages/about.html").await else {
-        let reponse_line = ResponseLine::new(
-            HttpVersion::new(HttpVersionEnum::V1_1),
-            Status::INTERNAL_SERVER_ERROR,
-        );
-
-        return ResponseMessage::new(reponse_line, request_message.header, Body::default());
-    };
+    let metadata = file
+        .metadata()
+        .await
+        .map_err(|_| Status::INTERNAL_SERVER_ERROR)?;
 
-    let response_line = ResponseLine::new(HttpVersion::new(HttpVersionEnum::V1_1), Status::OK);
+    let mut response = ResponseMessage::builder().content_type(ContentType::TEXT_HTML);
 
-    request_message.header.content_type = ContentType::TextHtml;
-    request_message.header.content_length = ContentLength::new(contents.len() as u64);
-
-    let Ok(body) = Body::parse(
-        contents.as_bytes().to_vec(),
-        &request_message.header.content_type,
-    ) else {
-        let response_line = ResponseLine::new(
-            HttpVersion::new(HttpVersionEnum::V1_1),
-            Status::INTERNAL_SERVER_ERROR,
-        );
-
-        return ResponseMessage::new(response_line, request_message.header, Body::default());
-    };
+    if let Ok(modified) = metadata.modified() {
+        response = response.last_modified(LastModified::new(modified));
+    }
 
-    ResponseMessage::new(response_line, request_message.header, body)
+    Ok(response.body(Body::new(BodyType::Stream(
+        BodyStream::from_reader(file).with_length(metadata.len()),
+    ))))
 }
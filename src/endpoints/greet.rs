@@ -0,0 +1,26 @@
+use serde::Deserialize;
+
+use http::types::{request::RequestMessage, status::Status};
+
+const DEFAULT_GREETING: &str = "Hello";
+
+#[derive(Deserialize)]
+struct GreetQuery {
+    greeting: Option<String>,
+}
+
+pub async fn handle(request_message: RequestMessage) -> Result<String, Status> {
+    let name = request_message
+        .path_param("name")
+        .ok_or(Status::INTERNAL_SERVER_ERROR)?;
+
+    let query = request_message
+        .request_line
+        .uri
+        .query_as::<GreetQuery>()
+        .map_err(|_| Status::BAD_REQUEST)?;
+
+    let greeting = query.greeting.as_deref().unwrap_or(DEFAULT_GREETING);
+
+    Ok(format!("{greeting}, {name}!"))
+}
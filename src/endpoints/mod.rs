@@ -1,3 +1,6 @@
 pub mod about;
 pub mod api;
+pub mod greet;
+pub mod report;
 pub mod root;
+pub mod upload;
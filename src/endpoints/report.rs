@@ -0,0 +1,30 @@
+use http::types::{
+    body::{Body, BodyStream, BodyType},
+    header::ContentType,
+    request::RequestMessage,
+    response::ResponseMessage,
+    response_header::CacheControl,
+};
+
+const REPORT_ROWS: u64 = 10_000;
+const REPORT_CHANNEL_BUFFER: usize = 16;
+
+pub async fn handle(_request_message: RequestMessage) -> ResponseMessage {
+    let (sender, stream) = BodyStream::channel(REPORT_CHANNEL_BUFFER);
+
+    tokio::spawn(async move {
+        for row in 0..REPORT_ROWS {
+            let line = format!("row {row}: {}\n", row * row);
+
+            if sender.send(line.into_bytes()).await.is_err() {
+                tracing::debug!("Report receiver dropped after {row} rows");
+                break;
+            }
+        }
+    });
+
+    ResponseMessage::builder()
+        .content_type(ContentType::TEXT_PLAIN)
+        .cache_control(CacheControl::new("no-store"))
+        .body(Body::new(BodyType::Stream(stream)))
+}
@@ -0,0 +1,54 @@
+use serde::Serialize;
+
+use http::{
+    multipart::{Limits, ParseError},
+    types::{body::Body, header::ContentType, request::RequestMessage, status::Status},
+};
+
+#[derive(Serialize)]
+struct UploadedPart {
+    name: Option<String>,
+    filename: Option<String>,
+    content_type: String,
+    size: u64,
+}
+
+pub async fn handle(request_message: RequestMessage) -> Result<serde_json::Value, Status> {
+    let RequestMessage { header, body, .. } = request_message;
+
+    let parts = read_parts(body, &header.content_type)
+        .await
+        .map_err(|err| match err {
+            ParseError::TooManyParts(_)
+            | ParseError::PartTooLarge(_)
+            | ParseError::BodyTooLarge(_) => Status::CONTENT_TOO_LARGE,
+            _ => Status::BAD_REQUEST,
+        })?;
+
+    serde_json::to_value(parts).map_err(|_| Status::INTERNAL_SERVER_ERROR)
+}
+
+// Parts are streamed through without being kept, only their sizes are reported
+async fn read_parts(
+    body: Body,
+    content_type: &ContentType,
+) -> Result<Vec<UploadedPart>, ParseError> {
+    let mut multipart = body.into_multipart(content_type, Limits::default())?;
+    let mut parts = Vec::new();
+
+    while let Some(mut part) = multipart.next_part().await? {
+        let name = part.name().map(ToOwned::to_owned);
+        let filename = part.filename().map(ToOwned::to_owned);
+        let content_type = part.content_type().mime_type().to_owned();
+        let size = part.copy_to(&mut tokio::io::sink()).await?;
+
+        parts.push(UploadedPart {
+            name,
+            filename,
+            content_type,
+            size,
+        });
+    }
+
+    Ok(parts)
+}
@@ -0,0 +1,93 @@
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use crate::types::{
+    body::{Body, BodyType},
+    header::ContentType,
+    response::ResponseMessage,
+    status::Status,
+};
+
+const ERROR_FORMAT_ENV: &str = "ERROR_FORMAT";
+const PLAIN_TEXT_ERROR_FORMAT_NAME: &str = "text";
+const PROBLEM_JSON_ERROR_FORMAT_NAME: &str = "json";
+
+// See RFC 9457 section 4.2.1
+const DEFAULT_PROBLEM_TYPE: &str = "about:blank";
+
+#[derive(Error, Debug)]
+pub enum ParseError {
+    #[error("Unknown error format: {0}")]
+    UnknownErrorFormat(String),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ErrorFormat {
+    #[default]
+    PlainText,
+    ProblemJson,
+}
+
+impl ErrorFormat {
+    /// Format named by the `ERROR_FORMAT` environment variable, `text` or `json`
+    pub fn from_env() -> Self {
+        std::env::var(ERROR_FORMAT_ENV)
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or_default()
+    }
+}
+
+impl FromStr for ErrorFormat {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            PLAIN_TEXT_ERROR_FORMAT_NAME => Ok(Self::PlainText),
+            PROBLEM_JSON_ERROR_FORMAT_NAME => Ok(Self::ProblemJson),
+            unknown => Err(Self::Err::UnknownErrorFormat(unknown.to_owned())),
+        }
+    }
+}
+
+pub fn error_response(
+    status: Status,
+    detail: Option<&str>,
+    format: ErrorFormat,
+) -> ResponseMessage {
+    let (content_type, body) = match format {
+        ErrorFormat::PlainText => {
+            let text = detail.map_or_else(
+                || status.to_string(),
+                |detail| format!("{status}: {detail}"),
+            );
+
+            (
+                ContentType::TEXT_PLAIN,
+                Body::new(BodyType::TextPlain(text)),
+            )
+        }
+        ErrorFormat::ProblemJson => {
+            let mut problem = serde_json::json!({
+                "type": DEFAULT_PROBLEM_TYPE,
+                "title": status.to_string(),
+                "status": status.status_code(),
+            });
+
+            if let Some(detail) = detail {
+                problem["detail"] = detail.into();
+            }
+
+            (
+                ContentType::APPLICATION_PROBLEM_JSON,
+                Body::new(BodyType::ApplicationJson(problem)),
+            )
+        }
+    };
+
+    ResponseMessage::builder()
+        .status(status)
+        .content_type(content_type)
+        .body(body)
+}
@@ -0,0 +1,42 @@
+use std::{future::Future, pin::Pin, sync::Arc};
+
+use crate::types::{
+    request::RequestMessage,
+    response::{IntoResponse, ResponseMessage},
+};
+
+pub type HandlerFuture<'a> = Pin<Box<dyn Future<Output = ResponseMessage> + Send + 'a>>;
+
+/// Produces the response to a request
+///
+/// Implemented for async functions and closures taking a [`RequestMessage`] and returning
+/// anything implementing [`IntoResponse`], so `async fn handle(request_message:
+/// RequestMessage) -> ResponseMessage` can be registered on a [`Router`](crate::Router) as is.
+/// Handlers can wrap other handlers, and a [`Router`](crate::Router) is a handler itself.
+pub trait Handler: Send + Sync + 'static {
+    fn call(&self, request_message: RequestMessage) -> HandlerFuture<'_>;
+}
+
+impl<F, Fut, R> Handler for F
+where
+    F: Fn(RequestMessage) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = R> + Send + 'static,
+    R: IntoResponse,
+{
+    fn call(&self, request_message: RequestMessage) -> HandlerFuture<'_> {
+        let response = self(request_message);
+        Box::pin(async move { response.await.into_response() })
+    }
+}
+
+impl<H: Handler + ?Sized> Handler for Arc<H> {
+    fn call(&self, request_message: RequestMessage) -> HandlerFuture<'_> {
+        (**self).call(request_message)
+    }
+}
+
+impl Handler for Box<dyn Handler> {
+    fn call(&self, request_message: RequestMessage) -> HandlerFuture<'_> {
+        (**self).call(request_message)
+    }
+}
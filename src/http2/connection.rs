@@ -0,0 +1,1555 @@
+use std::{
+    collections::{HashMap, HashSet},
+    future::Future,
+    io,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+};
+
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader},
+    sync::{mpsc, Mutex, Notify},
+    task::JoinSet,
+    time::Instant,
+};
+
+use super::{
+    frame::{self, ErrorCode, Frame, SettingId},
+    hpack, ConnectionError, PREFACE, PREFACE_REQUEST_LINE,
+};
+use crate::{
+    config::ConnectionConfig,
+    error,
+    request::{RequestMessageError, STREAMED_BODY_BUFFER},
+    response::Transport,
+    router::Router,
+    shutdown::Shutdown,
+    types::{
+        body::{Body, BodyStream, BodyType},
+        header::{ContentLength, Expect, Header, CONTENT_LENGTH_HEADER_NAME, HOST_HEADER_NAME},
+        header_map::HeaderMap,
+        request::RequestMessage,
+        request_line::{HttpVersion, HttpVersionEnum, RequestLine, RequestType},
+        response::{InterimSender, ResponseMessage},
+        response_header::{Date, Server},
+        status::Status,
+    },
+};
+
+const MAX_CONCURRENT_STREAMS: u32 = 100;
+// Number of frames read ahead of the connection loop
+const FRAME_BUFFER: usize = 16;
+const STATUS_PSEUDO_HEADER_NAME: &str = ":status";
+const TE_HEADER_NAME: &str = "te";
+const TE_TRAILERS: &str = "trailers";
+
+// Connection-specific fields are not allowed in HTTP/2, see RFC 9113 section 8.2.2
+const CONNECTION_SPECIFIC_HEADER_NAMES: [&str; 5] = [
+    "connection",
+    "keep-alive",
+    "proxy-connection",
+    "transfer-encoding",
+    "upgrade",
+];
+
+pub enum Handshake {
+    /// The client sent the preface right away, its first line was already read as a request line
+    PriorKnowledge,
+    /// The client switched protocols with this request, which is answered on stream 1
+    Upgrade {
+        request_message: Box<RequestMessage>,
+        settings: Vec<(SettingId, u32)>,
+    },
+}
+
+/// Send windows of the connection and its streams, see RFC 9113 section 5.2
+struct FlowControl {
+    connection_window: i64,
+    // Streams are removed once reset or answered, which stops any response still being sent
+    stream_windows: HashMap<u32, i64>,
+    initial_window_size: i64,
+    max_frame_size: u32,
+}
+
+/// Sending side of the connection, shared with the tasks answering streams
+struct Shared {
+    writer: Mutex<Box<dyn AsyncWrite + Send + Unpin>>,
+    flow_control: Mutex<FlowControl>,
+    window_updated: Notify,
+}
+
+impl Shared {
+    async fn write_frame(&self, frame: &Frame) -> io::Result<()> {
+        let mut writer = self.writer.lock().await;
+        writer.write_all(&frame.encode()).await?;
+        writer.flush().await
+    }
+
+    /// Writes a header block, returns false if the stream was reset in the meantime
+    async fn write_headers(
+        &self,
+        stream_id: u32,
+        fields: &[(String, String)],
+        end_stream: bool,
+    ) -> io::Result<bool> {
+        let block = hpack::encode(
+            fields
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_str())),
+        );
+
+        let max_frame_size = {
+            let flow_control = self.flow_control.lock().await;
+            if !flow_control.stream_windows.contains_key(&stream_id) {
+                return Ok(false);
+            }
+
+            flow_control.max_frame_size as usize
+        };
+
+        // A block split into CONTINUATION frames must not be interleaved with any other frame, so
+        // the writer is held until the whole block is written
+        let mut writer = self.writer.lock().await;
+        let fragments = block.len().div_ceil(max_frame_size);
+
+        for (index, fragment) in block.chunks(max_frame_size).enumerate() {
+            let end_headers = index + 1 == fragments;
+            let frame = if index == 0 {
+                Frame::Headers {
+                    stream_id,
+                    block: fragment.to_vec(),
+                    end_stream,
+                    end_headers,
+                }
+            } else {
+                Frame::Continuation {
+                    stream_id,
+                    block: fragment.to_vec(),
+                    end_headers,
+                }
+            };
+
+            writer.write_all(&frame.encode()).await?;
+        }
+
+        writer.flush().await?;
+        drop(writer);
+
+        Ok(true)
+    }
+
+    /// Gives back receive window of a stream, returns false if the stream was reset in the meantime
+    async fn write_window_update(&self, stream_id: u32, increment: u32) -> io::Result<bool> {
+        if !self
+            .flow_control
+            .lock()
+            .await
+            .stream_windows
+            .contains_key(&stream_id)
+        {
+            return Ok(false);
+        }
+
+        self.write_frame(&Frame::WindowUpdate {
+            stream_id,
+            increment,
+        })
+        .await?;
+
+        Ok(true)
+    }
+
+    /// Writes data in frames that fit the send windows, waiting for `WINDOW_UPDATE` whenever one of
+    /// them is exhausted. Returns false if the stream was reset in the meantime.
+    async fn write_data(
+        &self,
+        stream_id: u32,
+        mut data: &[u8],
+        end_stream: bool,
+    ) -> io::Result<bool> {
+        // Empty frames aren't subject to flow control
+        if data.is_empty() {
+            if end_stream {
+                self.write_frame(&Frame::Data {
+                    stream_id,
+                    data: Vec::new(),
+                    end_stream,
+                    flow_controlled_length: 0,
+                })
+                .await?;
+            }
+
+            return Ok(true);
+        }
+
+        while !data.is_empty() {
+            let Some(length) = self.reserve_capacity(stream_id, data.len()).await else {
+                return Ok(false);
+            };
+
+            let (chunk, rest) = data.split_at(length);
+            data = rest;
+
+            self.write_frame(&Frame::Data {
+                stream_id,
+                data: chunk.to_vec(),
+                end_stream: end_stream && data.is_empty(),
+                flow_controlled_length: 0,
+            })
+            .await?;
+        }
+
+        Ok(true)
+    }
+
+    async fn reserve_capacity(&self, stream_id: u32, wanted: usize) -> Option<usize> {
+        loop {
+            // Registered before checking the windows, so an update in between isn't missed
+            let window_updated = self.window_updated.notified();
+            tokio::pin!(window_updated);
+            window_updated.as_mut().enable();
+
+            let mut flow_control = self.flow_control.lock().await;
+            let limit = flow_control
+                .connection_window
+                .min(i64::from(flow_control.max_frame_size))
+                .min(i64::try_from(wanted).unwrap_or(i64::MAX));
+
+            let stream_window = flow_control.stream_windows.get_mut(&stream_id)?;
+            let available = limit.min(*stream_window);
+
+            if available > 0 {
+                *stream_window -= available;
+                flow_control.connection_window -= available;
+
+                return usize::try_from(available).ok();
+            }
+
+            drop(flow_control);
+            window_updated.await;
+        }
+    }
+}
+
+/// Request whose header block was received while its body is still arriving
+struct PendingRequest {
+    request_line: RequestLine,
+    header: Header,
+    body: Vec<u8>,
+    // Declared by the request, the DATA frames have to add up to it
+    content_length: Option<u64>,
+    // The whole body has to arrive by then
+    deadline: Instant,
+}
+
+/// Multipart body handed to its handler while it arrives, like a streamed HTTP/1.1 body
+struct StreamingBody {
+    chunks: mpsc::UnboundedSender<(Vec<u8>, u32)>,
+    // Flow-controlled bytes the handler didn't take yet, bounded by the stream window
+    unconsumed: Arc<AtomicU32>,
+    content_length: Option<u64>,
+    received: u64,
+    // Every frame of the body has to arrive by then
+    deadline: Instant,
+}
+
+/// Receiving side of a [`StreamingBody`], runs alongside the handler
+struct BodyForwarder {
+    chunks: mpsc::UnboundedReceiver<(Vec<u8>, u32)>,
+    sender: mpsc::Sender<Vec<u8>>,
+    unconsumed: Arc<AtomicU32>,
+}
+
+impl BodyForwarder {
+    /// Hands the body to the handler chunk by chunk, the stream window is only given back once a
+    /// chunk was taken so the client can't send faster than the handler reads
+    async fn forward(mut self, shared: &Shared, stream_id: u32) {
+        while let Some((chunk, flow_controlled_length)) = self.chunks.recv().await {
+            if self.sender.send(chunk).await.is_err() {
+                return;
+            }
+
+            self.unconsumed
+                .fetch_sub(flow_controlled_length, Ordering::Relaxed);
+
+            if flow_controlled_length == 0 {
+                continue;
+            }
+
+            match shared
+                .write_window_update(stream_id, flow_controlled_length)
+                .await
+            {
+                Ok(true) => {}
+                Ok(false) => return,
+                Err(err) => {
+                    tracing::debug!("Failed to update window of stream {stream_id}: {err:?}");
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Header block split across HEADERS and CONTINUATION frames
+struct HeaderBlock {
+    stream_id: u32,
+    block: Vec<u8>,
+    end_stream: bool,
+    // The rest of the block has to arrive by then
+    deadline: Instant,
+}
+
+struct Connection {
+    shared: Arc<Shared>,
+    config: Arc<ConnectionConfig>,
+    router: Arc<Router>,
+    decoder: hpack::Decoder,
+    receiving: HashMap<u32, PendingRequest>,
+    streaming: HashMap<u32, StreamingBody>,
+    // Streams that were answered before their body arrived, the rest of it is ignored
+    discarding: HashSet<u32>,
+    header_block: Option<HeaderBlock>,
+    // Tasks answering streams, each one yields its stream identifier
+    responding: JoinSet<u32>,
+    last_stream_id: u32,
+    going_away: bool,
+    shutdown: Shutdown,
+    served_streams: usize,
+}
+
+/// Serves an HTTP/2 connection until the client closes it or it stays idle for the keep-alive
+/// timeout, returns the number of streams answered
+pub async fn serve<S: Transport>(
+    reader: BufReader<S>,
+    config: Arc<ConnectionConfig>,
+    router: Arc<Router>,
+    handshake: Handshake,
+    shutdown: Shutdown,
+) -> Result<usize, ConnectionError> {
+    let (mut read_half, write_half) = tokio::io::split(reader);
+
+    let shared = Arc::new(Shared {
+        writer: Mutex::new(Box::new(write_half)),
+        flow_control: Mutex::new(FlowControl {
+            connection_window: i64::from(frame::DEFAULT_WINDOW_SIZE),
+            stream_windows: HashMap::new(),
+            initial_window_size: i64::from(frame::DEFAULT_WINDOW_SIZE),
+            max_frame_size: frame::DEFAULT_MAX_FRAME_SIZE,
+        }),
+        window_updated: Notify::new(),
+    });
+
+    // The server preface is a SETTINGS frame, see RFC 9113 section 3.4
+    shared
+        .write_frame(&Frame::Settings {
+            ack: false,
+            settings: vec![
+                (SettingId::MAX_CONCURRENT_STREAMS, MAX_CONCURRENT_STREAMS),
+                (
+                    SettingId::MAX_HEADER_LIST_SIZE,
+                    u32::try_from(config.max_header_size).unwrap_or(u32::MAX),
+                ),
+            ],
+        })
+        .await?;
+
+    let expected_preface = match handshake {
+        Handshake::PriorKnowledge => &PREFACE[PREFACE_REQUEST_LINE.len() + 2..],
+        Handshake::Upgrade { .. } => PREFACE,
+    };
+
+    if let Err(err) = read_preface(&mut read_half, expected_preface, &config).await {
+        go_away(&shared, 0, &err).await;
+        return Err(err);
+    }
+
+    let (frame_sender, frames) = mpsc::channel(FRAME_BUFFER);
+    let frame_reader = tokio::spawn(read_frames(read_half, frame_sender));
+
+    let mut connection = Connection {
+        shared: Arc::clone(&shared),
+        decoder: hpack::Decoder::new(hpack::DEFAULT_TABLE_SIZE, config.max_header_size),
+        config,
+        router,
+        receiving: HashMap::new(),
+        streaming: HashMap::new(),
+        discarding: HashSet::new(),
+        header_block: None,
+        responding: JoinSet::new(),
+        last_stream_id: 0,
+        going_away: false,
+        shutdown,
+        served_streams: 0,
+    };
+
+    let result = connection.run(frames, handshake).await;
+    frame_reader.abort();
+
+    if let Err(err) = &result {
+        go_away(&shared, connection.last_stream_id, err).await;
+    }
+
+    result
+}
+
+// The preface is bounded like the header section of an HTTP/1.1 request
+async fn read_preface<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    expected_preface: &[u8],
+    config: &ConnectionConfig,
+) -> Result<(), ConnectionError> {
+    let mut preface = vec![0u8; expected_preface.len()];
+    tokio::time::timeout(config.header_read_timeout, reader.read_exact(&mut preface))
+        .await
+        .map_err(|_| ConnectionError::PrefaceTimeout)??;
+
+    if preface != expected_preface {
+        return Err(ConnectionError::InvalidPreface);
+    }
+
+    Ok(())
+}
+
+async fn go_away(shared: &Shared, last_stream_id: u32, err: &ConnectionError) {
+    let Some(error_code) = err.error_code() else {
+        return;
+    };
+
+    tracing::debug!("Going away with {error_code}: {err}");
+    let go_away = Frame::GoAway {
+        last_stream_id,
+        error_code,
+        debug_data: err.to_string().into_bytes(),
+    };
+
+    if let Err(write_err) = shared.write_frame(&go_away).await {
+        tracing::debug!("Failed to send GOAWAY: {write_err:?}");
+    }
+}
+
+// Never completes without a deadline
+async fn sleep_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+async fn read_frames<R: AsyncRead + Unpin>(
+    mut reader: R,
+    frames: mpsc::Sender<Result<Frame, frame::ParseError>>,
+) {
+    loop {
+        let frame = Frame::read(&mut reader, frame::DEFAULT_MAX_FRAME_SIZE).await;
+
+        // The peer closing the connection is reported by dropping the sender
+        if matches!(&frame, Err(frame::ParseError::ReadError(err)) if err.kind() == io::ErrorKind::UnexpectedEof)
+        {
+            return;
+        }
+
+        let failed = frame.is_err();
+        if frames.send(frame).await.is_err() || failed {
+            return;
+        }
+    }
+}
+
+impl Connection {
+    async fn run(
+        &mut self,
+        mut frames: mpsc::Receiver<Result<Frame, frame::ParseError>>,
+        handshake: Handshake,
+    ) -> Result<usize, ConnectionError> {
+        if let Handshake::Upgrade {
+            request_message,
+            settings,
+        } = handshake
+        {
+            self.apply_settings(&settings).await?;
+            self.open_upgraded(request_message).await;
+        }
+
+        loop {
+            let idle = self.receiving.is_empty()
+                && self.responding.is_empty()
+                && self.header_block.is_none();
+            let deadline = self.next_deadline();
+
+            // After GOAWAY the streams already opened are still answered
+            if self.going_away && idle {
+                break;
+            }
+
+            tokio::select! {
+                frame = frames.recv() => match frame {
+                    Some(frame) => self.handle_frame(frame?).await?,
+                    None => break,
+                },
+                Some(joined) = self.responding.join_next() => match joined {
+                    Ok(stream_id) => {
+                        self.served_streams += 1;
+
+                        // The handler answered without reading the whole body, the rest of it
+                        // is ignored
+                        if self.streaming.remove(&stream_id).is_some() {
+                            self.discarding.insert(stream_id);
+                        }
+                    }
+                    Err(err) => tracing::error!("Stream task failed: {err:?}"),
+                },
+                // Streams opened so far are still answered, later ones are refused by GOAWAY
+                () = self.shutdown.triggered(), if !self.going_away => {
+                    tracing::debug!("Going away for shutdown after stream {}", self.last_stream_id);
+
+                    self.going_away = true;
+                    self.shared
+                        .write_frame(&Frame::GoAway {
+                            last_stream_id: self.last_stream_id,
+                            error_code: ErrorCode::NO_ERROR,
+                            debug_data: Vec::new(),
+                        })
+                        .await?;
+                }
+                () = sleep_until(deadline) => self.expire_deadlines().await?,
+                () = tokio::time::sleep(self.config.keep_alive_timeout), if idle => {
+                    tracing::debug!(
+                        "Keep-alive timeout expired after {} streams",
+                        self.served_streams
+                    );
+
+                    self.shared
+                        .write_frame(&Frame::GoAway {
+                            last_stream_id: self.last_stream_id,
+                            error_code: ErrorCode::NO_ERROR,
+                            debug_data: Vec::new(),
+                        })
+                        .await?;
+
+                    break;
+                }
+            }
+        }
+
+        Ok(self.served_streams)
+    }
+
+    async fn handle_frame(&mut self, frame: Frame) -> Result<(), ConnectionError> {
+        // Nothing may come between the frames of a header block, see RFC 9113 section 6.10
+        if let Some(header_block) = &self.header_block {
+            if !matches!(&frame, Frame::Continuation { stream_id, .. } if *stream_id == header_block.stream_id)
+            {
+                return Err(ConnectionError::ProtocolError(format!(
+                    "Expected CONTINUATION frame for stream {}",
+                    header_block.stream_id
+                )));
+            }
+        }
+
+        match frame {
+            Frame::Data {
+                stream_id,
+                data,
+                end_stream,
+                flow_controlled_length,
+            } => {
+                self.handle_data(stream_id, data, end_stream, flow_controlled_length)
+                    .await
+            }
+            Frame::Headers {
+                stream_id,
+                block,
+                end_stream,
+                end_headers,
+            } => {
+                let header_block = HeaderBlock {
+                    stream_id,
+                    block,
+                    end_stream,
+                    deadline: Instant::now() + self.config.header_read_timeout,
+                };
+
+                self.receive_header_block(header_block, end_headers).await
+            }
+            Frame::Continuation {
+                block, end_headers, ..
+            } => {
+                let Some(mut header_block) = self.header_block.take() else {
+                    return Err(ConnectionError::ProtocolError(
+                        "CONTINUATION frame without a header block".to_owned(),
+                    ));
+                };
+
+                header_block.block.extend_from_slice(&block);
+                self.receive_header_block(header_block, end_headers).await
+            }
+            Frame::RstStream {
+                stream_id,
+                error_code,
+            } => {
+                self.check_not_idle(stream_id)?;
+                tracing::debug!("Stream {stream_id} reset by peer with {error_code}");
+                self.close_stream(stream_id).await;
+                Ok(())
+            }
+            Frame::Settings {
+                ack: false,
+                settings,
+            } => {
+                self.apply_settings(&settings).await?;
+                self.shared
+                    .write_frame(&Frame::Settings {
+                        ack: true,
+                        settings: Vec::new(),
+                    })
+                    .await?;
+                Ok(())
+            }
+            Frame::Ping { ack: false, data } => {
+                self.shared
+                    .write_frame(&Frame::Ping { ack: true, data })
+                    .await?;
+                Ok(())
+            }
+            Frame::GoAway { error_code, .. } => {
+                tracing::debug!("Peer is going away with {error_code}");
+                self.going_away = true;
+                Ok(())
+            }
+            Frame::WindowUpdate {
+                stream_id,
+                increment,
+            } => self.handle_window_update(stream_id, increment).await,
+            Frame::PushPromise { .. } => Err(ConnectionError::ProtocolError(
+                "Clients can't push streams".to_owned(),
+            )),
+            Frame::Settings { ack: true, .. }
+            | Frame::Ping { ack: true, .. }
+            | Frame::Priority { .. }
+            | Frame::Unknown { .. } => Ok(()),
+        }
+    }
+
+    async fn handle_data(
+        &mut self,
+        stream_id: u32,
+        data: Vec<u8>,
+        end_stream: bool,
+        flow_controlled_length: u32,
+    ) -> Result<(), ConnectionError> {
+        self.check_not_idle(stream_id)?;
+
+        // Received data is consumed right away, so the windows are given back immediately
+        if flow_controlled_length > 0 {
+            self.shared
+                .write_frame(&Frame::WindowUpdate {
+                    stream_id: 0,
+                    increment: flow_controlled_length,
+                })
+                .await?;
+        }
+
+        // The stream window of an ignored body is never given back, so the client can't keep
+        // sending more than one window of it
+        if self.discarding.contains(&stream_id) {
+            if end_stream {
+                self.discarding.remove(&stream_id);
+            }
+
+            return Ok(());
+        }
+
+        if let Some(streaming_body) = self.streaming.get_mut(&stream_id) {
+            streaming_body.received += data.len() as u64;
+            if !fits_length(
+                streaming_body.content_length,
+                streaming_body.received,
+                end_stream,
+            ) {
+                return self
+                    .reset_stream(stream_id, ErrorCode::PROTOCOL_ERROR)
+                    .await;
+            }
+
+            let unconsumed = streaming_body
+                .unconsumed
+                .fetch_add(flow_controlled_length, Ordering::Relaxed)
+                + flow_controlled_length;
+
+            if unconsumed > frame::DEFAULT_WINDOW_SIZE {
+                return self
+                    .reset_stream(stream_id, ErrorCode::FLOW_CONTROL_ERROR)
+                    .await;
+            }
+
+            streaming_body.deadline = Instant::now() + self.config.body_read_timeout;
+
+            // A handler that is done already is noticed once its task is joined
+            let _ = streaming_body.chunks.send((data, flow_controlled_length));
+
+            if end_stream {
+                self.streaming.remove(&stream_id);
+            }
+
+            return Ok(());
+        }
+
+        let Some(pending_request) = self.receiving.get_mut(&stream_id) else {
+            return self.reset_stream(stream_id, ErrorCode::STREAM_CLOSED).await;
+        };
+
+        let received = (pending_request.body.len() + data.len()) as u64;
+        if !fits_length(pending_request.content_length, received, end_stream) {
+            return self
+                .reset_stream(stream_id, ErrorCode::PROTOCOL_ERROR)
+                .await;
+        }
+
+        if received > self.config.max_body_size {
+            return self.reject_body(stream_id, end_stream).await;
+        }
+
+        pending_request.body.extend_from_slice(&data);
+
+        if end_stream {
+            if let Some(pending_request) = self.receiving.remove(&stream_id) {
+                self.dispatch(stream_id, pending_request, HeaderMap::new());
+            }
+        } else if flow_controlled_length > 0 {
+            self.shared
+                .write_frame(&Frame::WindowUpdate {
+                    stream_id,
+                    increment: flow_controlled_length,
+                })
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    // Header blocks have to be buffered whole before they can be decoded, so their size is
+    // bounded while they arrive
+    async fn receive_header_block(
+        &mut self,
+        header_block: HeaderBlock,
+        end_headers: bool,
+    ) -> Result<(), ConnectionError> {
+        if header_block.block.len() > self.config.max_header_size {
+            return Err(ConnectionError::HeaderBlockTooLarge(
+                self.config.max_header_size,
+            ));
+        }
+
+        if end_headers {
+            self.handle_header_block(header_block).await
+        } else {
+            self.header_block = Some(header_block);
+            Ok(())
+        }
+    }
+
+    async fn handle_header_block(
+        &mut self,
+        header_block: HeaderBlock,
+    ) -> Result<(), ConnectionError> {
+        let HeaderBlock {
+            stream_id,
+            block,
+            end_stream,
+            ..
+        } = header_block;
+
+        // Blocks are decoded even for refused streams to keep the compression state in sync
+        let fields = match self.decoder.decode(&block) {
+            Ok(fields) => Ok(fields),
+            // The block was still decoded whole, so only the stream is affected
+            Err(hpack::DecodeError::HeaderListTooLarge(limit)) => {
+                Err(RequestMessageError::HeaderSectionTooLarge(limit))
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        // Trailers of a body that is being ignored are ignored along with it
+        if self.discarding.remove(&stream_id) {
+            return Ok(());
+        }
+
+        // The request was handed over already, so the trailers of a streamed body are dropped
+        if let Some(streaming_body) = self.streaming.remove(&stream_id) {
+            let received = streaming_body.received;
+            if !end_stream || !fits_length(streaming_body.content_length, received, true) {
+                return self
+                    .reset_stream(stream_id, ErrorCode::PROTOCOL_ERROR)
+                    .await;
+            }
+
+            return Ok(());
+        }
+
+        // A second header block carries trailers and has to end the stream, see RFC 9113 section
+        // 8.1
+        if let Some(pending_request) = self.receiving.remove(&stream_id) {
+            let fields = match fields {
+                Ok(fields) => fields,
+                Err(err) => {
+                    let head_only = pending_request.request_line.request_type == RequestType::Head;
+                    return self.respond_with_error(stream_id, head_only, &err).await;
+                }
+            };
+
+            let received = pending_request.body.len() as u64;
+            if !end_stream
+                || fields.iter().any(|(name, _)| name.starts_with(':'))
+                || !fits_length(pending_request.content_length, received, true)
+            {
+                return self
+                    .reset_stream(stream_id, ErrorCode::PROTOCOL_ERROR)
+                    .await;
+            }
+
+            let mut trailers = HeaderMap::new();
+            for (name, value) in fields {
+                trailers.append(&name, value);
+            }
+
+            self.dispatch(stream_id, pending_request, trailers);
+            return Ok(());
+        }
+
+        if stream_id <= self.last_stream_id {
+            return self.reset_stream(stream_id, ErrorCode::STREAM_CLOSED).await;
+        }
+
+        if stream_id % 2 == 0 {
+            return Err(ConnectionError::ProtocolError(format!(
+                "Client opened stream {stream_id} with an even identifier"
+            )));
+        }
+
+        self.last_stream_id = stream_id;
+
+        // Streams opened after GOAWAY are ignored, see RFC 9113 section 6.8
+        if self.going_away {
+            return Ok(());
+        }
+
+        self.open_stream(stream_id).await;
+
+        if self.receiving.len() + self.responding.len() >= MAX_CONCURRENT_STREAMS as usize {
+            return self
+                .reset_stream(stream_id, ErrorCode::REFUSED_STREAM)
+                .await;
+        }
+
+        self.open_request(stream_id, fields, end_stream).await
+    }
+
+    /// Answers the request a new stream opened with, or waits for its body
+    async fn open_request(
+        &mut self,
+        stream_id: u32,
+        fields: Result<Vec<(String, String)>, RequestMessageError>,
+        end_stream: bool,
+    ) -> Result<(), ConnectionError> {
+        let has_length = fields.as_ref().is_ok_and(|fields| {
+            fields
+                .iter()
+                .any(|(name, _)| name == CONTENT_LENGTH_HEADER_NAME)
+        });
+
+        let (request_line, header) =
+            match fields.and_then(|fields| request_head(fields, &self.config)) {
+                Ok(request_head) => request_head,
+                Err(err) => return self.respond_with_error(stream_id, false, &err).await,
+            };
+
+        let content_length = has_length.then(|| header.content_length.get());
+        if !fits_length(content_length, 0, end_stream) {
+            return self
+                .reset_stream(stream_id, ErrorCode::PROTOCOL_ERROR)
+                .await;
+        }
+
+        // Streamed bodies are never held in memory whole, their handler decides how much it accepts
+        let streamed = !end_stream && header.content_type.is_multipart();
+
+        // A declared length that is too large is refused before any of the body arrives
+        if !end_stream && !streamed && header.content_length.get() > self.config.max_body_size {
+            let head_only = request_line.request_type == RequestType::Head;
+            self.discarding.insert(stream_id);
+            let err = RequestMessageError::BodyTooLarge(self.config.max_body_size);
+            return self.respond_with_error(stream_id, head_only, &err).await;
+        }
+
+        if !end_stream {
+            if let Some(err) = self.refuse_expectation(&request_line, &header) {
+                let head_only = request_line.request_type == RequestType::Head;
+                self.discarding.insert(stream_id);
+                return self.respond_with_error(stream_id, head_only, &err).await;
+            }
+
+            // Clients that expect it wait for 100 Continue before sending the body
+            if header.expect().is_some() {
+                write_interim(
+                    &self.shared,
+                    stream_id,
+                    ResponseMessage::interim(Status::CONTINUE),
+                )
+                .await?;
+            }
+        }
+
+        if streamed {
+            self.stream_body(stream_id, request_line, header, content_length);
+            return Ok(());
+        }
+
+        let pending_request = PendingRequest {
+            request_line,
+            header,
+            body: Vec::new(),
+            content_length,
+            deadline: Instant::now() + self.config.body_read_timeout,
+        };
+
+        if end_stream {
+            self.dispatch(stream_id, pending_request, HeaderMap::new());
+        } else {
+            self.receiving.insert(stream_id, pending_request);
+        }
+
+        Ok(())
+    }
+
+    async fn handle_window_update(
+        &mut self,
+        stream_id: u32,
+        increment: u32,
+    ) -> Result<(), ConnectionError> {
+        self.check_not_idle(stream_id)?;
+
+        if increment == 0 {
+            if stream_id == 0 {
+                return Err(ConnectionError::ProtocolError(
+                    "WINDOW_UPDATE with zero increment".to_owned(),
+                ));
+            }
+
+            return self
+                .reset_stream(stream_id, ErrorCode::PROTOCOL_ERROR)
+                .await;
+        }
+
+        let max_window_size = i64::from(frame::MAX_WINDOW_SIZE);
+        let mut flow_control = self.shared.flow_control.lock().await;
+
+        if stream_id == 0 {
+            flow_control.connection_window += i64::from(increment);
+
+            if flow_control.connection_window > max_window_size {
+                return Err(ConnectionError::FlowControlError(
+                    "Connection window exceeds the maximum size".to_owned(),
+                ));
+            }
+        } else if let Some(stream_window) = flow_control.stream_windows.get_mut(&stream_id) {
+            *stream_window += i64::from(increment);
+
+            if *stream_window > max_window_size {
+                drop(flow_control);
+                return self
+                    .reset_stream(stream_id, ErrorCode::FLOW_CONTROL_ERROR)
+                    .await;
+            }
+        }
+
+        drop(flow_control);
+        self.shared.window_updated.notify_waiters();
+
+        Ok(())
+    }
+
+    async fn apply_settings(&self, settings: &[(SettingId, u32)]) -> Result<(), ConnectionError> {
+        let mut flow_control = self.shared.flow_control.lock().await;
+
+        for &(id, value) in settings {
+            match id {
+                SettingId::ENABLE_PUSH if value > 1 => {
+                    return Err(ConnectionError::ProtocolError(format!(
+                        "Invalid SETTINGS_ENABLE_PUSH value {value}"
+                    )));
+                }
+                SettingId::INITIAL_WINDOW_SIZE => {
+                    if value > frame::MAX_WINDOW_SIZE {
+                        return Err(ConnectionError::FlowControlError(format!(
+                            "Invalid SETTINGS_INITIAL_WINDOW_SIZE value {value}"
+                        )));
+                    }
+
+                    // Windows of open streams shift by the difference, see RFC 9113 section 6.9.2
+                    let delta = i64::from(value) - flow_control.initial_window_size;
+                    for stream_window in flow_control.stream_windows.values_mut() {
+                        *stream_window += delta;
+                    }
+
+                    flow_control.initial_window_size = i64::from(value);
+                }
+                SettingId::MAX_FRAME_SIZE => {
+                    if !(frame::DEFAULT_MAX_FRAME_SIZE..=frame::MAX_FRAME_SIZE_LIMIT)
+                        .contains(&value)
+                    {
+                        return Err(ConnectionError::ProtocolError(format!(
+                            "Invalid SETTINGS_MAX_FRAME_SIZE value {value}"
+                        )));
+                    }
+
+                    flow_control.max_frame_size = value;
+                }
+                // The encoder never uses the dynamic table and nothing is ever pushed, the
+                // remaining settings are advisory
+                _ => tracing::debug!("Ignoring {id} = {value}"),
+            }
+        }
+
+        drop(flow_control);
+        self.shared.window_updated.notify_waiters();
+
+        Ok(())
+    }
+
+    /// Answers the request that upgraded the connection on stream 1, see RFC 7540 section 3.2
+    async fn open_upgraded(&mut self, mut request_message: Box<RequestMessage>) {
+        request_message.request_line.http_version = HttpVersion::new(HttpVersionEnum::V2_0);
+
+        self.last_stream_id = 1;
+        self.open_stream(1).await;
+
+        self.spawn_handler(1, *request_message, None);
+    }
+
+    fn dispatch(&mut self, stream_id: u32, pending_request: PendingRequest, trailers: HeaderMap) {
+        let PendingRequest {
+            request_line,
+            header,
+            body,
+            ..
+        } = pending_request;
+
+        let mut request_message =
+            RequestMessage::new(request_line, header, Body::new(BodyType::Binary(body)));
+        request_message.trailers = trailers;
+
+        tracing::info!("Parsed request message on stream {stream_id}: {request_message:?}");
+
+        self.spawn_handler(stream_id, request_message, None);
+    }
+
+    /// Hands a multipart request to its handler right away, its body follows as it arrives
+    fn stream_body(
+        &mut self,
+        stream_id: u32,
+        request_line: RequestLine,
+        header: Header,
+        content_length: Option<u64>,
+    ) {
+        let (chunk_sender, chunks) = mpsc::unbounded_channel();
+        let (sender, body_stream) = BodyStream::channel(STREAMED_BODY_BUFFER);
+        let unconsumed = Arc::new(AtomicU32::new(0));
+
+        self.streaming.insert(
+            stream_id,
+            StreamingBody {
+                chunks: chunk_sender,
+                unconsumed: Arc::clone(&unconsumed),
+                content_length,
+                received: 0,
+                deadline: Instant::now() + self.config.body_read_timeout,
+            },
+        );
+
+        let request_message = RequestMessage::new(
+            request_line,
+            header,
+            Body::new(BodyType::Stream(body_stream)),
+        );
+
+        tracing::info!("Parsed request message on stream {stream_id}: {request_message:?}");
+
+        let forwarder = BodyForwarder {
+            chunks,
+            sender,
+            unconsumed,
+        };
+        self.spawn_handler(stream_id, request_message, Some(forwarder));
+    }
+
+    fn spawn_handler(
+        &mut self,
+        stream_id: u32,
+        mut request_message: RequestMessage,
+        forwarder: Option<BodyForwarder>,
+    ) {
+        let head_only = request_message.request_line.request_type == RequestType::Head;
+
+        let (interim, interim_responses) = InterimSender::channel();
+        request_message.interim = interim;
+
+        let router = Arc::clone(&self.router);
+        let shared = Arc::clone(&self.shared);
+        let response = async move {
+            let Some(forwarder) = forwarder else {
+                return router.handle(request_message).await;
+            };
+
+            // The handler reads the body while it's forwarded and may answer before it ended
+            let forwarding = async {
+                forwarder.forward(&shared, stream_id).await;
+                std::future::pending().await
+            };
+
+            tokio::select! {
+                response = router.handle(request_message) => response,
+                response = forwarding => response,
+            }
+        };
+
+        self.spawn_response(stream_id, head_only, response, Some(interim_responses));
+    }
+
+    /// Error to refuse a request with instead of letting the client send its body, see RFC 9110
+    /// section 10.1.1
+    fn refuse_expectation(
+        &self,
+        request_line: &RequestLine,
+        header: &Header,
+    ) -> Option<RequestMessageError> {
+        match header.expect()? {
+            Ok(Expect::Continue) => self
+                .router
+                .check_continue(request_line, header)
+                .err()
+                .map(RequestMessageError::ExpectationRejected),
+            Err(err) => Some(err.into()),
+        }
+    }
+
+    /// Earliest time by which a header block, a body or the next frame of a streamed body has to
+    /// arrive
+    fn next_deadline(&self) -> Option<Instant> {
+        let header_block_deadline = self
+            .header_block
+            .as_ref()
+            .map(|header_block| header_block.deadline);
+
+        self.receiving
+            .values()
+            .map(|pending_request| pending_request.deadline)
+            .chain(
+                self.streaming
+                    .values()
+                    .map(|streaming_body| streaming_body.deadline),
+            )
+            .chain(header_block_deadline)
+            .min()
+    }
+
+    /// A header block that stalls blocks the whole connection, a stalled body only its stream
+    async fn expire_deadlines(&mut self) -> Result<(), ConnectionError> {
+        let now = Instant::now();
+
+        if let Some(header_block) = &self.header_block {
+            if header_block.deadline <= now {
+                return Err(ConnectionError::HeaderBlockTimeout(header_block.stream_id));
+            }
+        }
+
+        let expired = self
+            .receiving
+            .iter()
+            .filter(|(_, pending_request)| pending_request.deadline <= now)
+            .map(|(&stream_id, _)| stream_id)
+            .collect::<Vec<_>>();
+
+        for stream_id in expired {
+            let Some(pending_request) = self.receiving.remove(&stream_id) else {
+                continue;
+            };
+
+            tracing::debug!("Body of stream {stream_id} was not received in time");
+
+            self.discarding.insert(stream_id);
+            let head_only = pending_request.request_line.request_type == RequestType::Head;
+            self.respond_with_error(stream_id, head_only, &RequestMessageError::BodyReadTimeout)
+                .await?;
+        }
+
+        // Streamed bodies belong to a handler already, which can't be answered for
+        let expired = self
+            .streaming
+            .iter()
+            .filter(|(_, streaming_body)| streaming_body.deadline <= now)
+            .map(|(&stream_id, _)| stream_id)
+            .collect::<Vec<_>>();
+
+        for stream_id in expired {
+            tracing::debug!("Body of stream {stream_id} was not received in time");
+            self.reset_stream(stream_id, ErrorCode::CANCEL).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Answers a request whose body outgrew the limit, the rest of the body is ignored
+    async fn reject_body(
+        &mut self,
+        stream_id: u32,
+        end_stream: bool,
+    ) -> Result<(), ConnectionError> {
+        let Some(pending_request) = self.receiving.remove(&stream_id) else {
+            return Ok(());
+        };
+
+        if !end_stream {
+            self.discarding.insert(stream_id);
+        }
+
+        let head_only = pending_request.request_line.request_type == RequestType::Head;
+        let err = RequestMessageError::BodyTooLarge(self.config.max_body_size);
+        self.respond_with_error(stream_id, head_only, &err).await
+    }
+
+    async fn respond_with_error(
+        &mut self,
+        stream_id: u32,
+        head_only: bool,
+        err: &RequestMessageError,
+    ) -> Result<(), ConnectionError> {
+        let Some(status) = err.status() else {
+            tracing::debug!("Malformed request on stream {stream_id}: {err}");
+            return self
+                .reset_stream(stream_id, ErrorCode::PROTOCOL_ERROR)
+                .await;
+        };
+
+        let response = error::error_response(status, err.detail(), self.router.get_error_format());
+        self.spawn_response(stream_id, head_only, async move { response }, None);
+
+        Ok(())
+    }
+
+    fn spawn_response(
+        &mut self,
+        stream_id: u32,
+        head_only: bool,
+        response: impl Future<Output = ResponseMessage> + Send + 'static,
+        interim_responses: Option<mpsc::UnboundedReceiver<ResponseMessage>>,
+    ) {
+        let shared = Arc::clone(&self.shared);
+
+        self.responding.spawn(async move {
+            let response = match interim_responses {
+                Some(interim_responses) => {
+                    write_interims(&shared, stream_id, response, interim_responses).await
+                }
+                None => response.await,
+            };
+            tracing::info!("Generated response message on stream {stream_id} as {response:?}");
+
+            if let Err(err) = write_response(&shared, stream_id, response, head_only).await {
+                tracing::error!("Failed to write response on stream {stream_id}: {err:?}");
+            }
+
+            shared
+                .flow_control
+                .lock()
+                .await
+                .stream_windows
+                .remove(&stream_id);
+
+            stream_id
+        });
+    }
+
+    async fn open_stream(&self, stream_id: u32) {
+        let mut flow_control = self.shared.flow_control.lock().await;
+        let initial_window_size = flow_control.initial_window_size;
+        flow_control
+            .stream_windows
+            .insert(stream_id, initial_window_size);
+    }
+
+    /// Forgets the stream, a response that is still being sent stops at its next frame
+    async fn close_stream(&mut self, stream_id: u32) {
+        self.receiving.remove(&stream_id);
+        self.streaming.remove(&stream_id);
+        self.discarding.remove(&stream_id);
+        self.shared
+            .flow_control
+            .lock()
+            .await
+            .stream_windows
+            .remove(&stream_id);
+        self.shared.window_updated.notify_waiters();
+    }
+
+    async fn reset_stream(
+        &mut self,
+        stream_id: u32,
+        error_code: ErrorCode,
+    ) -> Result<(), ConnectionError> {
+        tracing::debug!("Resetting stream {stream_id} with {error_code}");
+        self.close_stream(stream_id).await;
+        self.shared
+            .write_frame(&Frame::RstStream {
+                stream_id,
+                error_code,
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    /// Frames other than HEADERS and PRIORITY on streams that were never opened are a connection
+    /// error, see RFC 9113 section 5.1
+    fn check_not_idle(&self, stream_id: u32) -> Result<(), ConnectionError> {
+        if stream_id > self.last_stream_id {
+            return Err(ConnectionError::ProtocolError(format!(
+                "Frame received on idle stream {stream_id}"
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether the DATA received so far agree with the declared content-length, a request whose
+/// payloads add up to a different length is malformed, see RFC 9113 section 8.1.1
+fn fits_length(content_length: Option<u64>, received: u64, end_stream: bool) -> bool {
+    content_length.is_none_or(|content_length| {
+        received <= content_length && (!end_stream || received == content_length)
+    })
+}
+
+/// Request line and header fields from the fields of a header block, see RFC 9113 section 8.3
+fn request_head(
+    fields: Vec<(String, String)>,
+    config: &ConnectionConfig,
+) -> Result<(RequestLine, Header), RequestMessageError> {
+    let malformed =
+        |reason: String| RequestMessageError::from(ConnectionError::ProtocolError(reason));
+
+    let header_count = fields
+        .iter()
+        .filter(|(name, _)| !name.starts_with(':'))
+        .count();
+    if header_count > config.max_header_count {
+        return Err(RequestMessageError::TooManyHeaders(config.max_header_count));
+    }
+
+    let mut method = None;
+    let mut scheme = None;
+    let mut authority = None;
+    let mut path = None;
+    let mut header_map = HeaderMap::new();
+
+    for (name, value) in fields {
+        if let Some(pseudo_header) = name.strip_prefix(':') {
+            // Pseudo-header fields come before all regular ones and appear at most once
+            if !header_map.is_empty() {
+                return Err(malformed(format!("{name} after regular fields")));
+            }
+
+            let slot = match pseudo_header {
+                "method" => &mut method,
+                "scheme" => &mut scheme,
+                "authority" => &mut authority,
+                "path" => &mut path,
+                _ => return Err(malformed(format!("Unknown pseudo-header {name}"))),
+            };
+
+            if slot.replace(value).is_some() {
+                return Err(malformed(format!("Duplicate pseudo-header {name}")));
+            }
+
+            continue;
+        }
+
+        if name.bytes().any(|byte| byte.is_ascii_uppercase()) {
+            return Err(malformed(format!("Uppercase field name {name}")));
+        }
+
+        if CONNECTION_SPECIFIC_HEADER_NAMES.contains(&name.as_str())
+            || (name == TE_HEADER_NAME && value != TE_TRAILERS)
+        {
+            return Err(malformed(format!("Connection-specific field {name}")));
+        }
+
+        header_map.append(&name, value);
+    }
+
+    let (Some(method), Some(_), Some(path)) = (method, scheme, path) else {
+        return Err(malformed("Missing pseudo-header".to_owned()));
+    };
+
+    // The authority takes the role of Host, see RFC 9113 section 8.3.1
+    if let Some(authority) = authority {
+        header_map.insert(HOST_HEADER_NAME, authority);
+    }
+
+    let request_line = RequestLine::new(
+        method.parse()?,
+        path.parse()?,
+        HttpVersion::new(HttpVersionEnum::V2_0),
+    )?;
+    let header = Header::try_from(&mut header_map)?;
+
+    Ok((request_line, header))
+}
+
+/// Awaits the final response, writing the interim responses sent in the meantime
+async fn write_interims(
+    shared: &Shared,
+    stream_id: u32,
+    response: impl Future<Output = ResponseMessage>,
+    mut interim_responses: mpsc::UnboundedReceiver<ResponseMessage>,
+) -> ResponseMessage {
+    tokio::pin!(response);
+
+    let response = loop {
+        tokio::select! {
+            response = &mut response => break response,
+            Some(interim) = interim_responses.recv() => {
+                if let Err(err) = write_interim(shared, stream_id, interim).await {
+                    tracing::debug!("Failed to write interim response on stream {stream_id}: {err:?}");
+                }
+            }
+        }
+    };
+
+    while let Ok(interim) = interim_responses.try_recv() {
+        if let Err(err) = write_interim(shared, stream_id, interim).await {
+            tracing::debug!("Failed to write interim response on stream {stream_id}: {err:?}");
+        }
+    }
+
+    response
+}
+
+// Interim responses are header blocks that don't end the stream, see RFC 9113 section 8.1
+async fn write_interim(
+    shared: &Shared,
+    stream_id: u32,
+    response: ResponseMessage,
+) -> io::Result<()> {
+    // A second final header block would be malformed
+    let status = &response.response_line.status;
+    if !status.is_interim() {
+        tracing::warn!(
+            "Dropping interim response with status {}",
+            status.status_code()
+        );
+        return Ok(());
+    }
+
+    let fields = response_fields(&response);
+    shared.write_headers(stream_id, &fields, false).await?;
+    Ok(())
+}
+
+// The status becomes a pseudo-header, fields specific to HTTP/1 connections are dropped
+fn response_fields(response: &ResponseMessage) -> Vec<(String, String)> {
+    let mut fields = vec![(
+        STATUS_PSEUDO_HEADER_NAME.to_owned(),
+        response.response_line.status.status_code().to_string(),
+    )];
+    fields.extend(
+        HeaderMap::from(&response.header)
+            .iter()
+            .filter(|(name, _)| !CONNECTION_SPECIFIC_HEADER_NAMES.contains(name))
+            .map(|(name, value)| (name.to_owned(), value.to_owned())),
+    );
+
+    fields
+}
+
+async fn write_response(
+    shared: &Shared,
+    stream_id: u32,
+    mut response: ResponseMessage,
+    head_only: bool,
+) -> io::Result<()> {
+    response.header.date.get_or_insert_with(Date::now);
+    response.header.server.get_or_insert_with(Server::default);
+
+    // Streams carry their own framing, a length is only announced when it's known upfront and
+    // always matches the body, see RFC 9110 section 8.6
+    response.header.transfer_encoding = None;
+    let allows_content = response.response_line.status.allows_content();
+    response.header.content_length = match response.body.get_type() {
+        _ if !allows_content => None,
+        BodyType::Stream(body_stream) => body_stream.length().map(ContentLength::new),
+        body_type => Some(ContentLength::new(body_type.as_bytes().len() as u64)),
+    };
+    let head_only = head_only || !allows_content;
+
+    let fields = response_fields(&response);
+
+    if head_only {
+        shared.write_headers(stream_id, &fields, true).await?;
+        return Ok(());
+    }
+
+    match response.body.get_type_mut() {
+        BodyType::Stream(body_stream) => {
+            if !shared.write_headers(stream_id, &fields, false).await? {
+                return Ok(());
+            }
+
+            // Bytes beyond the announced length are dropped, see RFC 9113 section 8.1.1
+            let mut remaining = body_stream.length();
+
+            while remaining != Some(0) {
+                let Some(mut chunk) = body_stream.next_chunk().await? else {
+                    break;
+                };
+
+                if let Some(remaining) = &mut remaining {
+                    chunk.truncate(usize::try_from(*remaining).unwrap_or(usize::MAX));
+                    *remaining -= chunk.len() as u64;
+                }
+
+                if !shared.write_data(stream_id, &chunk, false).await? {
+                    return Ok(());
+                }
+            }
+
+            // A stream that ended before its length can't be completed, so it's reset instead
+            if remaining.is_some_and(|remaining| remaining > 0) {
+                tracing::warn!("Body stream of stream {stream_id} ended before its length");
+                return shared
+                    .write_frame(&Frame::RstStream {
+                        stream_id,
+                        error_code: ErrorCode::INTERNAL_ERROR,
+                    })
+                    .await;
+            }
+
+            shared.write_data(stream_id, &[], true).await?;
+        }
+        body_type => {
+            let body = body_type.as_bytes();
+
+            let written = shared
+                .write_headers(stream_id, &fields, body.is_empty())
+                .await?;
+
+            if written && !body.is_empty() {
+                shared.write_data(stream_id, &body, true).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
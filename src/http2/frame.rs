@@ -0,0 +1,673 @@
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+const FRAME_HEADER_LENGTH: usize = 9;
+const SETTING_LENGTH: usize = 6;
+const STREAM_ID_MASK: u32 = 0x7fff_ffff;
+
+const DATA_FRAME_TYPE: u8 = 0x0;
+const HEADERS_FRAME_TYPE: u8 = 0x1;
+const PRIORITY_FRAME_TYPE: u8 = 0x2;
+const RST_STREAM_FRAME_TYPE: u8 = 0x3;
+const SETTINGS_FRAME_TYPE: u8 = 0x4;
+const PUSH_PROMISE_FRAME_TYPE: u8 = 0x5;
+const PING_FRAME_TYPE: u8 = 0x6;
+const GOAWAY_FRAME_TYPE: u8 = 0x7;
+const WINDOW_UPDATE_FRAME_TYPE: u8 = 0x8;
+const CONTINUATION_FRAME_TYPE: u8 = 0x9;
+
+const END_STREAM_FLAG: u8 = 0x1;
+const ACK_FLAG: u8 = 0x1;
+const END_HEADERS_FLAG: u8 = 0x4;
+const PADDED_FLAG: u8 = 0x8;
+const PRIORITY_FLAG: u8 = 0x20;
+
+pub const DEFAULT_MAX_FRAME_SIZE: u32 = 16_384;
+pub const MAX_FRAME_SIZE_LIMIT: u32 = 16_777_215;
+pub const DEFAULT_WINDOW_SIZE: u32 = 65_535;
+pub const MAX_WINDOW_SIZE: u32 = 2_147_483_647;
+
+/// Error codes of `RST_STREAM` and `GOAWAY` frames, see RFC 9113 section 7
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorCode(u32);
+
+impl ErrorCode {
+    pub const NO_ERROR: Self = Self(0x0);
+    pub const PROTOCOL_ERROR: Self = Self(0x1);
+    pub const INTERNAL_ERROR: Self = Self(0x2);
+    pub const FLOW_CONTROL_ERROR: Self = Self(0x3);
+    pub const SETTINGS_TIMEOUT: Self = Self(0x4);
+    pub const STREAM_CLOSED: Self = Self(0x5);
+    pub const FRAME_SIZE_ERROR: Self = Self(0x6);
+    pub const REFUSED_STREAM: Self = Self(0x7);
+    pub const CANCEL: Self = Self(0x8);
+    pub const COMPRESSION_ERROR: Self = Self(0x9);
+    pub const CONNECT_ERROR: Self = Self(0xa);
+    pub const ENHANCE_YOUR_CALM: Self = Self(0xb);
+    pub const INADEQUATE_SECURITY: Self = Self(0xc);
+    pub const HTTP_1_1_REQUIRED: Self = Self(0xd);
+}
+
+/// Identifiers of SETTINGS parameters, see RFC 9113 section 6.5.2
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SettingId(u16);
+
+impl SettingId {
+    pub const HEADER_TABLE_SIZE: Self = Self(0x1);
+    pub const ENABLE_PUSH: Self = Self(0x2);
+    pub const MAX_CONCURRENT_STREAMS: Self = Self(0x3);
+    pub const INITIAL_WINDOW_SIZE: Self = Self(0x4);
+    pub const MAX_FRAME_SIZE: Self = Self(0x5);
+    pub const MAX_HEADER_LIST_SIZE: Self = Self(0x6);
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match *self {
+            Self::NO_ERROR => "NO_ERROR",
+            Self::PROTOCOL_ERROR => "PROTOCOL_ERROR",
+            Self::INTERNAL_ERROR => "INTERNAL_ERROR",
+            Self::FLOW_CONTROL_ERROR => "FLOW_CONTROL_ERROR",
+            Self::SETTINGS_TIMEOUT => "SETTINGS_TIMEOUT",
+            Self::STREAM_CLOSED => "STREAM_CLOSED",
+            Self::FRAME_SIZE_ERROR => "FRAME_SIZE_ERROR",
+            Self::REFUSED_STREAM => "REFUSED_STREAM",
+            Self::CANCEL => "CANCEL",
+            Self::COMPRESSION_ERROR => "COMPRESSION_ERROR",
+            Self::CONNECT_ERROR => "CONNECT_ERROR",
+            Self::ENHANCE_YOUR_CALM => "ENHANCE_YOUR_CALM",
+            Self::INADEQUATE_SECURITY => "INADEQUATE_SECURITY",
+            Self::HTTP_1_1_REQUIRED => "HTTP_1_1_REQUIRED",
+            // Unknown codes must not trigger special behavior, see RFC 9113 section 7
+            Self(code) => return write!(f, "unknown error code {code:#x}"),
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+impl std::fmt::Display for SettingId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match *self {
+            Self::HEADER_TABLE_SIZE => "SETTINGS_HEADER_TABLE_SIZE",
+            Self::ENABLE_PUSH => "SETTINGS_ENABLE_PUSH",
+            Self::MAX_CONCURRENT_STREAMS => "SETTINGS_MAX_CONCURRENT_STREAMS",
+            Self::INITIAL_WINDOW_SIZE => "SETTINGS_INITIAL_WINDOW_SIZE",
+            Self::MAX_FRAME_SIZE => "SETTINGS_MAX_FRAME_SIZE",
+            Self::MAX_HEADER_LIST_SIZE => "SETTINGS_MAX_HEADER_LIST_SIZE",
+            Self(id) => return write!(f, "unknown setting {id:#x}"),
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ParseError {
+    #[error("Failed to read frame: {0:?}")]
+    ReadError(#[from] std::io::Error),
+
+    #[error("Frame of type {kind:#x} has invalid size {length}")]
+    InvalidFrameSize { kind: u8, length: usize },
+
+    #[error("Frame of type {0:#x} is not allowed on stream {1}")]
+    InvalidStreamId(u8, u32),
+
+    #[error("Padding exceeds the frame payload")]
+    InvalidPadding,
+}
+
+#[derive(Debug)]
+pub enum Frame {
+    Data {
+        stream_id: u32,
+        data: Vec<u8>,
+        end_stream: bool,
+        // Padding counts towards flow control even though it's discarded
+        flow_controlled_length: u32,
+    },
+    Headers {
+        stream_id: u32,
+        block: Vec<u8>,
+        end_stream: bool,
+        end_headers: bool,
+    },
+    Priority {
+        stream_id: u32,
+        dependency: u32,
+    },
+    RstStream {
+        stream_id: u32,
+        error_code: ErrorCode,
+    },
+    Settings {
+        ack: bool,
+        settings: Vec<(SettingId, u32)>,
+    },
+    PushPromise {
+        stream_id: u32,
+    },
+    Ping {
+        ack: bool,
+        data: [u8; 8],
+    },
+    GoAway {
+        last_stream_id: u32,
+        error_code: ErrorCode,
+        debug_data: Vec<u8>,
+    },
+    WindowUpdate {
+        stream_id: u32,
+        increment: u32,
+    },
+    Continuation {
+        stream_id: u32,
+        block: Vec<u8>,
+        end_headers: bool,
+    },
+    // Frames of unknown types must be ignored, see RFC 9113 section 4.1
+    Unknown {
+        kind: u8,
+    },
+}
+
+impl Frame {
+    pub const fn stream_id(&self) -> u32 {
+        match self {
+            Self::Data { stream_id, .. }
+            | Self::Headers { stream_id, .. }
+            | Self::Priority { stream_id, .. }
+            | Self::RstStream { stream_id, .. }
+            | Self::PushPromise { stream_id }
+            | Self::WindowUpdate { stream_id, .. }
+            | Self::Continuation { stream_id, .. } => *stream_id,
+            Self::Settings { .. }
+            | Self::Ping { .. }
+            | Self::GoAway { .. }
+            | Self::Unknown { .. } => 0,
+        }
+    }
+
+    /// Reads one frame whose payload is at most `max_frame_size` bytes
+    pub async fn read<R: AsyncRead + Unpin>(
+        reader: &mut R,
+        max_frame_size: u32,
+    ) -> Result<Self, ParseError> {
+        let mut header = [0u8; FRAME_HEADER_LENGTH];
+        reader.read_exact(&mut header).await?;
+
+        let length = u32::from_be_bytes([0, header[0], header[1], header[2]]);
+        let kind = header[3];
+        let flags = header[4];
+        let stream_id =
+            u32::from_be_bytes([header[5], header[6], header[7], header[8]]) & STREAM_ID_MASK;
+
+        if length > max_frame_size {
+            return Err(ParseError::InvalidFrameSize {
+                kind,
+                length: length as usize,
+            });
+        }
+
+        let mut payload = vec![0u8; length as usize];
+        reader.read_exact(&mut payload).await?;
+
+        Self::parse(kind, flags, stream_id, payload)
+    }
+
+    fn parse(kind: u8, flags: u8, stream_id: u32, payload: Vec<u8>) -> Result<Self, ParseError> {
+        let length = payload.len();
+        let invalid_size = || ParseError::InvalidFrameSize { kind, length };
+
+        let connection_level = matches!(
+            kind,
+            SETTINGS_FRAME_TYPE | PING_FRAME_TYPE | GOAWAY_FRAME_TYPE
+        );
+        let stream_level = matches!(
+            kind,
+            DATA_FRAME_TYPE
+                | HEADERS_FRAME_TYPE
+                | PRIORITY_FRAME_TYPE
+                | RST_STREAM_FRAME_TYPE
+                | PUSH_PROMISE_FRAME_TYPE
+                | CONTINUATION_FRAME_TYPE
+        );
+        if (connection_level && stream_id != 0) || (stream_level && stream_id == 0) {
+            return Err(ParseError::InvalidStreamId(kind, stream_id));
+        }
+
+        Ok(match kind {
+            DATA_FRAME_TYPE => Self::Data {
+                stream_id,
+                data: strip_padding(flags, &payload)?.to_vec(),
+                end_stream: flags & END_STREAM_FLAG != 0,
+                flow_controlled_length: u32::try_from(length).map_err(|_| invalid_size())?,
+            },
+            HEADERS_FRAME_TYPE => {
+                let mut block = strip_padding(flags, &payload)?;
+
+                // Stream dependency and weight are deprecated and skipped, see RFC 9113 section 5.3.2
+                if flags & PRIORITY_FLAG != 0 {
+                    block = block.get(5..).ok_or_else(invalid_size)?;
+                }
+
+                Self::Headers {
+                    stream_id,
+                    block: block.to_vec(),
+                    end_stream: flags & END_STREAM_FLAG != 0,
+                    end_headers: flags & END_HEADERS_FLAG != 0,
+                }
+            }
+            PRIORITY_FRAME_TYPE => {
+                let [a, b, c, d, _weight] = payload[..] else {
+                    return Err(invalid_size());
+                };
+
+                Self::Priority {
+                    stream_id,
+                    dependency: u32::from_be_bytes([a, b, c, d]) & STREAM_ID_MASK,
+                }
+            }
+            RST_STREAM_FRAME_TYPE => Self::RstStream {
+                stream_id,
+                error_code: ErrorCode(read_u32(&payload).ok_or_else(invalid_size)?),
+            },
+            SETTINGS_FRAME_TYPE => {
+                let ack = flags & ACK_FLAG != 0;
+                if ack && length != 0 {
+                    return Err(invalid_size());
+                }
+
+                Self::Settings {
+                    ack,
+                    settings: parse_settings(&payload).ok_or_else(invalid_size)?,
+                }
+            }
+            PUSH_PROMISE_FRAME_TYPE => Self::PushPromise { stream_id },
+            PING_FRAME_TYPE => Self::Ping {
+                ack: flags & ACK_FLAG != 0,
+                data: payload[..].try_into().map_err(|_| invalid_size())?,
+            },
+            GOAWAY_FRAME_TYPE => {
+                if length < 8 {
+                    return Err(invalid_size());
+                }
+
+                Self::GoAway {
+                    last_stream_id: read_u32(&payload[..4]).ok_or_else(invalid_size)?
+                        & STREAM_ID_MASK,
+                    error_code: ErrorCode(read_u32(&payload[4..8]).ok_or_else(invalid_size)?),
+                    debug_data: payload[8..].to_vec(),
+                }
+            }
+            WINDOW_UPDATE_FRAME_TYPE => Self::WindowUpdate {
+                stream_id,
+                increment: read_u32(&payload).ok_or_else(invalid_size)? & STREAM_ID_MASK,
+            },
+            CONTINUATION_FRAME_TYPE => Self::Continuation {
+                stream_id,
+                block: payload,
+                end_headers: flags & END_HEADERS_FLAG != 0,
+            },
+            kind => Self::Unknown { kind },
+        })
+    }
+
+    /// Serializes the frame, the caller is responsible for keeping it within the peer's maximum
+    /// frame size
+    pub fn encode(&self) -> Vec<u8> {
+        let (kind, flags, payload) = match self {
+            Self::Data {
+                data, end_stream, ..
+            } => (
+                DATA_FRAME_TYPE,
+                flag(*end_stream, END_STREAM_FLAG),
+                data.clone(),
+            ),
+            Self::Headers {
+                block,
+                end_stream,
+                end_headers,
+                ..
+            } => (
+                HEADERS_FRAME_TYPE,
+                flag(*end_stream, END_STREAM_FLAG) | flag(*end_headers, END_HEADERS_FLAG),
+                block.clone(),
+            ),
+            Self::Priority { dependency, .. } => {
+                let mut payload = dependency.to_be_bytes().to_vec();
+                payload.push(0);
+                (PRIORITY_FRAME_TYPE, 0, payload)
+            }
+            Self::RstStream { error_code, .. } => (
+                RST_STREAM_FRAME_TYPE,
+                0,
+                error_code.0.to_be_bytes().to_vec(),
+            ),
+            Self::Settings { ack, settings } => (
+                SETTINGS_FRAME_TYPE,
+                flag(*ack, ACK_FLAG),
+                settings
+                    .iter()
+                    .flat_map(|(id, value)| {
+                        id.0.to_be_bytes().into_iter().chain(value.to_be_bytes())
+                    })
+                    .collect(),
+            ),
+            Self::PushPromise { .. } => (PUSH_PROMISE_FRAME_TYPE, 0, Vec::new()),
+            Self::Ping { ack, data } => (PING_FRAME_TYPE, flag(*ack, ACK_FLAG), data.to_vec()),
+            Self::GoAway {
+                last_stream_id,
+                error_code,
+                debug_data,
+            } => {
+                let mut payload = last_stream_id.to_be_bytes().to_vec();
+                payload.extend_from_slice(&error_code.0.to_be_bytes());
+                payload.extend_from_slice(debug_data);
+                (GOAWAY_FRAME_TYPE, 0, payload)
+            }
+            Self::WindowUpdate { increment, .. } => (
+                WINDOW_UPDATE_FRAME_TYPE,
+                0,
+                increment.to_be_bytes().to_vec(),
+            ),
+            Self::Continuation {
+                block, end_headers, ..
+            } => (
+                CONTINUATION_FRAME_TYPE,
+                flag(*end_headers, END_HEADERS_FLAG),
+                block.clone(),
+            ),
+            Self::Unknown { kind } => (*kind, 0, Vec::new()),
+        };
+
+        let length = u32::try_from(payload.len())
+            .unwrap_or(MAX_FRAME_SIZE_LIMIT)
+            .to_be_bytes();
+
+        let mut frame = Vec::with_capacity(FRAME_HEADER_LENGTH + payload.len());
+        frame.extend_from_slice(&length[1..]);
+        frame.push(kind);
+        frame.push(flags);
+        frame.extend_from_slice(&self.stream_id().to_be_bytes());
+        frame.extend_from_slice(&payload);
+
+        frame
+    }
+}
+
+/// Parameters of a SETTINGS payload, also used for the `HTTP2-Settings` field of h2c upgrades
+pub fn parse_settings(payload: &[u8]) -> Option<Vec<(SettingId, u32)>> {
+    if !payload.len().is_multiple_of(SETTING_LENGTH) {
+        return None;
+    }
+
+    let settings = payload
+        .chunks_exact(SETTING_LENGTH)
+        .map(|setting| {
+            (
+                SettingId(u16::from_be_bytes([setting[0], setting[1]])),
+                u32::from_be_bytes([setting[2], setting[3], setting[4], setting[5]]),
+            )
+        })
+        .collect();
+
+    Some(settings)
+}
+
+const fn flag(set: bool, flag: u8) -> u8 {
+    if set {
+        flag
+    } else {
+        0
+    }
+}
+
+fn read_u32(payload: &[u8]) -> Option<u32> {
+    payload.try_into().ok().map(u32::from_be_bytes)
+}
+
+fn strip_padding(flags: u8, payload: &[u8]) -> Result<&[u8], ParseError> {
+    if flags & PADDED_FLAG == 0 {
+        return Ok(payload);
+    }
+
+    let (&padding, rest) = payload.split_first().ok_or(ParseError::InvalidPadding)?;
+
+    rest.len()
+        .checked_sub(usize::from(padding))
+        .map(|length| &rest[..length])
+        .ok_or(ParseError::InvalidPadding)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Reading the encoded frame back and encoding it again yields the same bytes
+    async fn round_trip(frame: &Frame) -> Frame {
+        let encoded = frame.encode();
+        let decoded = Frame::read(&mut &encoded[..], MAX_FRAME_SIZE_LIMIT)
+            .await
+            .unwrap();
+
+        assert_eq!(decoded.encode(), encoded, "{frame:?}");
+        decoded
+    }
+
+    #[tokio::test]
+    async fn round_trips_every_frame_type() {
+        let frames = [
+            Frame::Data {
+                stream_id: 1,
+                data: b"hello".to_vec(),
+                end_stream: true,
+                flow_controlled_length: 5,
+            },
+            Frame::Headers {
+                stream_id: 3,
+                block: vec![0x82, 0x86, 0x84],
+                end_stream: false,
+                end_headers: true,
+            },
+            Frame::Priority {
+                stream_id: 5,
+                dependency: 3,
+            },
+            Frame::RstStream {
+                stream_id: 7,
+                error_code: ErrorCode::CANCEL,
+            },
+            Frame::Settings {
+                ack: false,
+                settings: vec![
+                    (SettingId::MAX_CONCURRENT_STREAMS, 100),
+                    (SettingId::INITIAL_WINDOW_SIZE, MAX_WINDOW_SIZE),
+                ],
+            },
+            Frame::Settings {
+                ack: true,
+                settings: Vec::new(),
+            },
+            Frame::Ping {
+                ack: true,
+                data: *b"pingpong",
+            },
+            Frame::GoAway {
+                last_stream_id: 9,
+                error_code: ErrorCode::ENHANCE_YOUR_CALM,
+                debug_data: b"calm down".to_vec(),
+            },
+            Frame::WindowUpdate {
+                stream_id: 0,
+                increment: DEFAULT_WINDOW_SIZE,
+            },
+            Frame::Continuation {
+                stream_id: 11,
+                block: vec![0x88],
+                end_headers: true,
+            },
+        ];
+
+        for frame in &frames {
+            round_trip(frame).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn round_trips_fields_of_data_and_headers() {
+        let data = Frame::Data {
+            stream_id: 1,
+            data: vec![0; 100],
+            end_stream: false,
+            flow_controlled_length: 100,
+        };
+        assert!(matches!(
+            round_trip(&data).await,
+            Frame::Data {
+                stream_id: 1,
+                data,
+                end_stream: false,
+                flow_controlled_length: 100,
+            } if data.len() == 100
+        ));
+
+        let headers = Frame::Headers {
+            stream_id: 1,
+            block: vec![0x88],
+            end_stream: true,
+            end_headers: false,
+        };
+        assert!(matches!(
+            round_trip(&headers).await,
+            Frame::Headers {
+                stream_id: 1,
+                block,
+                end_stream: true,
+                end_headers: false,
+            } if block == [0x88]
+        ));
+    }
+
+    #[test]
+    fn strips_padding_but_counts_it_for_flow_control() {
+        let frame = Frame::parse(DATA_FRAME_TYPE, PADDED_FLAG, 1, vec![2, b'a', b'b', 0, 0]);
+
+        assert!(matches!(
+            frame,
+            Ok(Frame::Data {
+                data,
+                flow_controlled_length: 5,
+                ..
+            }) if data == b"ab"
+        ));
+    }
+
+    #[test]
+    fn rejects_padding_longer_than_the_payload() {
+        let frame = Frame::parse(HEADERS_FRAME_TYPE, PADDED_FLAG, 1, vec![4, 0x82, 0]);
+
+        assert!(matches!(frame, Err(ParseError::InvalidPadding)));
+    }
+
+    #[test]
+    fn skips_the_priority_of_headers() {
+        let frame = Frame::parse(
+            HEADERS_FRAME_TYPE,
+            PRIORITY_FLAG | END_HEADERS_FLAG,
+            3,
+            vec![0, 0, 0, 1, 16, 0x82],
+        );
+
+        assert!(matches!(
+            frame,
+            Ok(Frame::Headers {
+                block,
+                end_headers: true,
+                ..
+            }) if block == [0x82]
+        ));
+    }
+
+    #[tokio::test]
+    async fn rejects_frames_over_the_maximum_size() {
+        let encoded = Frame::Data {
+            stream_id: 1,
+            data: vec![0; 17],
+            end_stream: false,
+            flow_controlled_length: 17,
+        }
+        .encode();
+
+        assert!(matches!(
+            Frame::read(&mut &encoded[..], 16).await,
+            Err(ParseError::InvalidFrameSize {
+                kind: DATA_FRAME_TYPE,
+                length: 17,
+            })
+        ));
+    }
+
+    #[test]
+    fn rejects_frames_on_the_wrong_stream() {
+        assert!(matches!(
+            Frame::parse(SETTINGS_FRAME_TYPE, 0, 1, Vec::new()),
+            Err(ParseError::InvalidStreamId(SETTINGS_FRAME_TYPE, 1))
+        ));
+        assert!(matches!(
+            Frame::parse(DATA_FRAME_TYPE, 0, 0, Vec::new()),
+            Err(ParseError::InvalidStreamId(DATA_FRAME_TYPE, 0))
+        ));
+    }
+
+    #[test]
+    fn rejects_payloads_of_the_wrong_size() {
+        for (kind, flags, payload) in [
+            (SETTINGS_FRAME_TYPE, 0, vec![0; 5]),
+            (SETTINGS_FRAME_TYPE, ACK_FLAG, vec![0; 6]),
+            (PING_FRAME_TYPE, 0, vec![0; 7]),
+            (GOAWAY_FRAME_TYPE, 0, vec![0; 7]),
+            (WINDOW_UPDATE_FRAME_TYPE, 0, vec![0; 3]),
+        ] {
+            assert!(matches!(
+                Frame::parse(kind, flags, 0, payload),
+                Err(ParseError::InvalidFrameSize { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn keeps_frames_of_unknown_types() {
+        assert!(matches!(
+            Frame::parse(0xfa, 0xff, 0, vec![1, 2, 3]),
+            Ok(Frame::Unknown { kind: 0xfa })
+        ));
+    }
+
+    #[test]
+    fn parses_settings_of_upgrade_requests() {
+        let settings = parse_settings(&[0, 3, 0, 0, 0, 100, 0, 4, 0, 1, 0, 0]).unwrap();
+
+        assert_eq!(
+            settings,
+            [
+                (SettingId::MAX_CONCURRENT_STREAMS, 100),
+                (SettingId::INITIAL_WINDOW_SIZE, 65_536),
+            ]
+        );
+        assert!(parse_settings(&[0, 3, 0]).is_none());
+    }
+
+    #[test]
+    fn displays_error_codes_and_setting_ids() {
+        assert_eq!(
+            ErrorCode::FLOW_CONTROL_ERROR.to_string(),
+            "FLOW_CONTROL_ERROR"
+        );
+        assert_eq!(ErrorCode(0xff).to_string(), "unknown error code 0xff");
+        assert_eq!(
+            SettingId::MAX_FRAME_SIZE.to_string(),
+            "SETTINGS_MAX_FRAME_SIZE"
+        );
+    }
+}
@@ -0,0 +1,765 @@
+use std::collections::VecDeque;
+
+use thiserror::Error;
+
+use super::huffman;
+
+// Every entry of the dynamic table is accounted with an extra overhead, see RFC 7541 section 4.1
+const ENTRY_OVERHEAD: usize = 32;
+pub const DEFAULT_TABLE_SIZE: usize = 4096;
+
+// Static table from RFC 7541 Appendix A, indexed from 1
+const STATIC_TABLE: [(&str, &str); 61] = [
+    (":authority", ""),
+    (":method", "GET"),
+    (":method", "POST"),
+    (":path", "/"),
+    (":path", "/index.html"),
+    (":scheme", "http"),
+    (":scheme", "https"),
+    (":status", "200"),
+    (":status", "204"),
+    (":status", "206"),
+    (":status", "304"),
+    (":status", "400"),
+    (":status", "404"),
+    (":status", "500"),
+    ("accept-charset", ""),
+    ("accept-encoding", "gzip, deflate"),
+    ("accept-language", ""),
+    ("accept-ranges", ""),
+    ("accept", ""),
+    ("access-control-allow-origin", ""),
+    ("age", ""),
+    ("allow", ""),
+    ("authorization", ""),
+    ("cache-control", ""),
+    ("content-disposition", ""),
+    ("content-encoding", ""),
+    ("content-language", ""),
+    ("content-length", ""),
+    ("content-location", ""),
+    ("content-range", ""),
+    ("content-type", ""),
+    ("cookie", ""),
+    ("date", ""),
+    ("etag", ""),
+    ("expect", ""),
+    ("expires", ""),
+    ("from", ""),
+    ("host", ""),
+    ("if-match", ""),
+    ("if-modified-since", ""),
+    ("if-none-match", ""),
+    ("if-range", ""),
+    ("if-unmodified-since", ""),
+    ("last-modified", ""),
+    ("link", ""),
+    ("location", ""),
+    ("max-forwards", ""),
+    ("proxy-authenticate", ""),
+    ("proxy-authorization", ""),
+    ("range", ""),
+    ("referer", ""),
+    ("refresh", ""),
+    ("retry-after", ""),
+    ("server", ""),
+    ("set-cookie", ""),
+    ("strict-transport-security", ""),
+    ("transfer-encoding", ""),
+    ("user-agent", ""),
+    ("vary", ""),
+    ("via", ""),
+    ("www-authenticate", ""),
+];
+
+#[derive(Error, Debug)]
+pub enum DecodeError {
+    #[error("Header block ended unexpectedly")]
+    UnexpectedEnd,
+
+    #[error("Integer exceeds the supported range")]
+    IntegerOverflow,
+
+    #[error("Invalid table index: {0}")]
+    InvalidIndex(usize),
+
+    #[error("Table size update to {0} exceeds the allowed maximum")]
+    InvalidTableSize(usize),
+
+    #[error("Table size update after the start of the header block")]
+    UnexpectedTableSizeUpdate,
+
+    #[error("Field is not valid UTF-8")]
+    InvalidUtf8(#[from] std::string::FromUtf8Error),
+
+    #[error("Huffman decode error: {0:?}")]
+    HuffmanError(#[from] huffman::DecodeError),
+
+    #[error("Header list exceeds the limit of {0} bytes")]
+    HeaderListTooLarge(usize),
+}
+
+/// Table of fields added by previous header blocks, newest first
+#[derive(Debug)]
+struct DynamicTable {
+    entries: VecDeque<(String, String)>,
+    size: usize,
+    max_size: usize,
+}
+
+impl DynamicTable {
+    const fn new(max_size: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            size: 0,
+            max_size,
+        }
+    }
+
+    fn get(&self, index: usize) -> Result<(&str, &str), DecodeError> {
+        let entry = match index {
+            0 => None,
+            1..=61 => STATIC_TABLE.get(index - 1).copied(),
+            _ => self
+                .entries
+                .get(index - STATIC_TABLE.len() - 1)
+                .map(|(name, value)| (name.as_str(), value.as_str())),
+        };
+
+        entry.ok_or(DecodeError::InvalidIndex(index))
+    }
+
+    fn insert(&mut self, name: String, value: String) {
+        let entry_size = name.len() + value.len() + ENTRY_OVERHEAD;
+
+        self.size += entry_size;
+        self.entries.push_front((name, value));
+        // An entry larger than the whole table empties it without being added
+        self.evict();
+    }
+
+    fn resize(&mut self, max_size: usize) {
+        self.max_size = max_size;
+        self.evict();
+    }
+
+    fn evict(&mut self) {
+        while self.size > self.max_size {
+            let Some((name, value)) = self.entries.pop_back() else {
+                break;
+            };
+
+            self.size -= name.len() + value.len() + ENTRY_OVERHEAD;
+        }
+    }
+}
+
+/// Decodes header blocks of one connection, see RFC 7541 section 6
+#[derive(Debug)]
+pub struct Decoder {
+    table: DynamicTable,
+    max_table_size: usize,
+    max_header_list_size: usize,
+}
+
+impl Decoder {
+    pub const fn new(max_table_size: usize, max_header_list_size: usize) -> Self {
+        Self {
+            table: DynamicTable::new(max_table_size),
+            max_table_size,
+            max_header_list_size,
+        }
+    }
+
+    /// Decodes a whole header block, whose header list may take at most the configured size
+    ///
+    /// Indexed fields let a small block expand into a huge list, so the size is counted while
+    /// decoding, see RFC 9113 section 6.5.2. A block over the limit is still decoded to the end
+    /// without keeping its fields, the dynamic table then stays in sync for the next blocks.
+    pub fn decode(&mut self, mut block: &[u8]) -> Result<Vec<(String, String)>, DecodeError> {
+        let mut fields = Vec::new();
+        let mut header_list_size = 0;
+        let mut started = false;
+
+        while let Some(&first) = block.first() {
+            let field = if first & 0b1000_0000 != 0 {
+                // Indexed field
+                let index = decode_integer(&mut block, 7)?;
+                let (name, value) = self.table.get(index)?;
+                header_list_size += name.len() + value.len() + ENTRY_OVERHEAD;
+
+                (header_list_size <= self.max_header_list_size)
+                    .then(|| (name.to_owned(), value.to_owned()))
+            } else if first & 0b0100_0000 != 0 {
+                // Literal with incremental indexing
+                let (name, value) = self.decode_literal(&mut block, 6)?;
+                header_list_size += name.len() + value.len() + ENTRY_OVERHEAD;
+                self.table.insert(name.clone(), value.clone());
+
+                Some((name, value))
+            } else if first & 0b0010_0000 != 0 {
+                // Dynamic table size updates are only allowed at the start of a block
+                if started {
+                    return Err(DecodeError::UnexpectedTableSizeUpdate);
+                }
+
+                let max_size = decode_integer(&mut block, 5)?;
+                if max_size > self.max_table_size {
+                    return Err(DecodeError::InvalidTableSize(max_size));
+                }
+
+                self.table.resize(max_size);
+                continue;
+            } else {
+                // Literal without indexing or never indexed, both use a 4 bit prefix
+                let (name, value) = self.decode_literal(&mut block, 4)?;
+                header_list_size += name.len() + value.len() + ENTRY_OVERHEAD;
+
+                Some((name, value))
+            };
+
+            started = true;
+
+            if header_list_size <= self.max_header_list_size {
+                fields.extend(field);
+            } else {
+                // The rest of the block is only decoded to keep the dynamic table in sync
+                fields = Vec::new();
+            }
+        }
+
+        if header_list_size > self.max_header_list_size {
+            return Err(DecodeError::HeaderListTooLarge(self.max_header_list_size));
+        }
+
+        Ok(fields)
+    }
+
+    fn decode_literal(
+        &self,
+        block: &mut &[u8],
+        prefix: u8,
+    ) -> Result<(String, String), DecodeError> {
+        let index = decode_integer(block, prefix)?;
+
+        let name = if index == 0 {
+            decode_string(block)?
+        } else {
+            self.table.get(index)?.0.to_owned()
+        };
+
+        Ok((name, decode_string(block)?))
+    }
+}
+
+/// Encodes a header block without ever adding entries to the dynamic table, so the peer's table
+/// stays empty and no size updates are needed
+pub fn encode<'a>(fields: impl IntoIterator<Item = (&'a str, &'a str)>) -> Vec<u8> {
+    let mut block = Vec::new();
+
+    for (name, value) in fields {
+        let full_match = STATIC_TABLE
+            .iter()
+            .position(|&entry| entry == (name, value));
+
+        if let Some(position) = full_match {
+            encode_integer(&mut block, 0b1000_0000, 7, position + 1);
+            continue;
+        }
+
+        // Literal without indexing, referring to the name in the static table when possible
+        if let Some(position) = STATIC_TABLE.iter().position(|&(entry, _)| entry == name) {
+            encode_integer(&mut block, 0, 4, position + 1);
+        } else {
+            block.push(0);
+            encode_string(&mut block, name);
+        }
+
+        encode_string(&mut block, value);
+    }
+
+    block
+}
+
+/// Integer with an N-bit prefix, see RFC 7541 section 5.1
+fn decode_integer(block: &mut &[u8], prefix: u8) -> Result<usize, DecodeError> {
+    let (&first, rest) = block.split_first().ok_or(DecodeError::UnexpectedEnd)?;
+    *block = rest;
+
+    let max_prefix = u8::MAX >> (8 - prefix);
+    let mut value = usize::from(first & max_prefix);
+    if value < usize::from(max_prefix) {
+        return Ok(value);
+    }
+
+    let mut shift = 0;
+    loop {
+        let (&byte, rest) = block.split_first().ok_or(DecodeError::UnexpectedEnd)?;
+        *block = rest;
+
+        if shift > usize::BITS - 7 {
+            return Err(DecodeError::IntegerOverflow);
+        }
+
+        value = value
+            .checked_add(usize::from(byte & 0b0111_1111) << shift)
+            .ok_or(DecodeError::IntegerOverflow)?;
+        shift += 7;
+
+        if byte & 0b1000_0000 == 0 {
+            return Ok(value);
+        }
+    }
+}
+
+// Every cast below is of a value that was checked or masked to fit in the prefix or 7 bits
+#[allow(clippy::cast_possible_truncation)]
+fn encode_integer(block: &mut Vec<u8>, flags: u8, prefix: u8, mut value: usize) {
+    let max_prefix = u8::MAX >> (8 - prefix);
+
+    if value < usize::from(max_prefix) {
+        block.push(flags | value as u8);
+        return;
+    }
+
+    block.push(flags | max_prefix);
+    value -= usize::from(max_prefix);
+
+    while value >= 0b1000_0000 {
+        block.push(0b1000_0000 | (value & 0b0111_1111) as u8);
+        value >>= 7;
+    }
+
+    block.push(value as u8);
+}
+
+/// String literal, optionally Huffman encoded, see RFC 7541 section 5.2
+fn decode_string(block: &mut &[u8]) -> Result<String, DecodeError> {
+    let huffman = block.first().ok_or(DecodeError::UnexpectedEnd)? & 0b1000_0000 != 0;
+    let length = decode_integer(block, 7)?;
+
+    if block.len() < length {
+        return Err(DecodeError::UnexpectedEnd);
+    }
+
+    let (data, rest) = block.split_at(length);
+    *block = rest;
+
+    let data = if huffman {
+        huffman::decode(data)?
+    } else {
+        data.to_vec()
+    };
+
+    Ok(String::from_utf8(data)?)
+}
+
+fn encode_string(block: &mut Vec<u8>, value: &str) {
+    encode_integer(block, 0, 7, value.len());
+    block.extend_from_slice(value.as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAX_HEADER_LIST_SIZE: usize = 16 * 1024;
+
+    fn hex(encoded: &str) -> Vec<u8> {
+        let digits = encoded
+            .chars()
+            .filter(|char| !char.is_whitespace())
+            .collect::<Vec<_>>();
+
+        digits
+            .chunks(2)
+            .map(|pair| u8::from_str_radix(&pair.iter().collect::<String>(), 16).unwrap())
+            .collect()
+    }
+
+    fn fields(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|&(name, value)| (name.to_owned(), value.to_owned()))
+            .collect()
+    }
+
+    fn table(decoder: &Decoder) -> Vec<(&str, &str)> {
+        decoder
+            .table
+            .entries
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect()
+    }
+
+    // Header block, decoded fields and dynamic table afterwards, see RFC 7541 Appendix C
+    type Example<'a> = (&'a str, &'a [(&'a str, &'a str)], &'a [(&'a str, &'a str)]);
+
+    fn assert_decodes(max_table_size: usize, examples: &[Example<'_>]) {
+        let mut decoder = Decoder::new(max_table_size, MAX_HEADER_LIST_SIZE);
+
+        for &(block, expected_fields, expected_table) in examples {
+            assert_eq!(
+                decoder.decode(&hex(block)).unwrap(),
+                fields(expected_fields)
+            );
+            assert_eq!(table(&decoder), expected_table);
+
+            let expected_size = expected_table
+                .iter()
+                .map(|(name, value)| name.len() + value.len() + ENTRY_OVERHEAD)
+                .sum::<usize>();
+            assert_eq!(decoder.table.size, expected_size);
+        }
+    }
+
+    const REQUEST_TABLES: [&[(&str, &str)]; 3] = [
+        &[(":authority", "www.example.com")],
+        &[
+            ("cache-control", "no-cache"),
+            (":authority", "www.example.com"),
+        ],
+        &[
+            ("custom-key", "custom-value"),
+            ("cache-control", "no-cache"),
+            (":authority", "www.example.com"),
+        ],
+    ];
+
+    const REQUEST_FIELDS: [&[(&str, &str)]; 3] = [
+        &[
+            (":method", "GET"),
+            (":scheme", "http"),
+            (":path", "/"),
+            (":authority", "www.example.com"),
+        ],
+        &[
+            (":method", "GET"),
+            (":scheme", "http"),
+            (":path", "/"),
+            (":authority", "www.example.com"),
+            ("cache-control", "no-cache"),
+        ],
+        &[
+            (":method", "GET"),
+            (":scheme", "https"),
+            (":path", "/index.html"),
+            (":authority", "www.example.com"),
+            ("custom-key", "custom-value"),
+        ],
+    ];
+
+    const RESPONSE_FIELDS: [&[(&str, &str)]; 3] = [
+        &[
+            (":status", "302"),
+            ("cache-control", "private"),
+            ("date", "Mon, 21 Oct 2013 20:13:21 GMT"),
+            ("location", "https://www.example.com"),
+        ],
+        &[
+            (":status", "307"),
+            ("cache-control", "private"),
+            ("date", "Mon, 21 Oct 2013 20:13:21 GMT"),
+            ("location", "https://www.example.com"),
+        ],
+        &[
+            (":status", "200"),
+            ("cache-control", "private"),
+            ("date", "Mon, 21 Oct 2013 20:13:22 GMT"),
+            ("location", "https://www.example.com"),
+            ("content-encoding", "gzip"),
+            (
+                "set-cookie",
+                "foo=ASDJKHQKBZXOQWEOPIUAXQWEOIU; max-age=3600; version=1",
+            ),
+        ],
+    ];
+
+    // Entries are evicted as the 256 byte table fills up
+    const RESPONSE_TABLES: [&[(&str, &str)]; 3] = [
+        &[
+            ("location", "https://www.example.com"),
+            ("date", "Mon, 21 Oct 2013 20:13:21 GMT"),
+            ("cache-control", "private"),
+            (":status", "302"),
+        ],
+        &[
+            (":status", "307"),
+            ("location", "https://www.example.com"),
+            ("date", "Mon, 21 Oct 2013 20:13:21 GMT"),
+            ("cache-control", "private"),
+        ],
+        &[
+            (
+                "set-cookie",
+                "foo=ASDJKHQKBZXOQWEOPIUAXQWEOIU; max-age=3600; version=1",
+            ),
+            ("content-encoding", "gzip"),
+            ("date", "Mon, 21 Oct 2013 20:13:22 GMT"),
+        ],
+    ];
+
+    #[test]
+    fn decodes_integers() {
+        // RFC 7541 Appendix C.1
+        for (encoded, prefix, value) in [("0a", 5, 10), ("1f9a0a", 5, 1337), ("2a", 8, 42)] {
+            let encoded = hex(encoded);
+            let mut block = &encoded[..];
+
+            assert_eq!(decode_integer(&mut block, prefix).unwrap(), value);
+            assert!(block.is_empty());
+
+            let mut reencoded = Vec::new();
+            encode_integer(&mut reencoded, 0, prefix, value);
+            assert_eq!(reencoded, encoded);
+        }
+    }
+
+    #[test]
+    fn rejects_integer_overflow() {
+        let encoded = hex("1fffffffffffffffffffff7f");
+
+        assert!(matches!(
+            decode_integer(&mut &encoded[..], 5),
+            Err(DecodeError::IntegerOverflow)
+        ));
+    }
+
+    #[test]
+    fn decodes_field_representations() {
+        // RFC 7541 Appendix C.2, each example starts with an empty table
+        assert_decodes(
+            DEFAULT_TABLE_SIZE,
+            &[(
+                "400a 6375 7374 6f6d 2d6b 6579 0d63 7573 746f 6d2d 6865 6164 6572",
+                &[("custom-key", "custom-header")],
+                &[("custom-key", "custom-header")],
+            )],
+        );
+        assert_decodes(
+            DEFAULT_TABLE_SIZE,
+            &[(
+                "040c 2f73 616d 706c 652f 7061 7468",
+                &[(":path", "/sample/path")],
+                &[],
+            )],
+        );
+        assert_decodes(
+            DEFAULT_TABLE_SIZE,
+            &[(
+                "1008 7061 7373 776f 7264 0673 6563 7265 74",
+                &[("password", "secret")],
+                &[],
+            )],
+        );
+        assert_decodes(DEFAULT_TABLE_SIZE, &[("82", &[(":method", "GET")], &[])]);
+    }
+
+    #[test]
+    fn decodes_requests_without_huffman() {
+        // RFC 7541 Appendix C.3
+        assert_decodes(
+            DEFAULT_TABLE_SIZE,
+            &[
+                (
+                    "8286 8441 0f77 7777 2e65 7861 6d70 6c65 2e63 6f6d",
+                    REQUEST_FIELDS[0],
+                    REQUEST_TABLES[0],
+                ),
+                (
+                    "8286 84be 5808 6e6f 2d63 6163 6865",
+                    REQUEST_FIELDS[1],
+                    REQUEST_TABLES[1],
+                ),
+                (
+                    "8287 85bf 400a 6375 7374 6f6d 2d6b 6579 0c63 7573 746f 6d2d 7661 6c75 65",
+                    REQUEST_FIELDS[2],
+                    REQUEST_TABLES[2],
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn decodes_requests_with_huffman() {
+        // RFC 7541 Appendix C.4
+        assert_decodes(
+            DEFAULT_TABLE_SIZE,
+            &[
+                (
+                    "8286 8441 8cf1 e3c2 e5f2 3a6b a0ab 90f4 ff",
+                    REQUEST_FIELDS[0],
+                    REQUEST_TABLES[0],
+                ),
+                (
+                    "8286 84be 5886 a8eb 1064 9cbf",
+                    REQUEST_FIELDS[1],
+                    REQUEST_TABLES[1],
+                ),
+                (
+                    "8287 85bf 4088 25a8 49e9 5ba9 7d7f 8925 a849 e95b b8e8 b4bf",
+                    REQUEST_FIELDS[2],
+                    REQUEST_TABLES[2],
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn decodes_responses_without_huffman() {
+        // RFC 7541 Appendix C.5
+        assert_decodes(
+            256,
+            &[
+                (
+                    "4803 3330 3258 0770 7269 7661 7465 611d 4d6f 6e2c 2032 3120 4f63 7420 3230
+                     3133 2032 303a 3133 3a32 3120 474d 546e 1768 7474 7073 3a2f 2f77 7777 2e65
+                     7861 6d70 6c65 2e63 6f6d",
+                    RESPONSE_FIELDS[0],
+                    RESPONSE_TABLES[0],
+                ),
+                (
+                    "4803 3330 37c1 c0bf",
+                    RESPONSE_FIELDS[1],
+                    RESPONSE_TABLES[1],
+                ),
+                (
+                    "88c1 611d 4d6f 6e2c 2032 3120 4f63 7420 3230 3133 2032 303a 3133 3a32 3220
+                     474d 54c0 5a04 677a 6970 7738 666f 6f3d 4153 444a 4b48 514b 425a 584f 5157
+                     454f 5049 5541 5851 5745 4f49 553b 206d 6178 2d61 6765 3d33 3630 303b 2076
+                     6572 7369 6f6e 3d31",
+                    RESPONSE_FIELDS[2],
+                    RESPONSE_TABLES[2],
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn decodes_responses_with_huffman() {
+        // RFC 7541 Appendix C.6
+        assert_decodes(
+            256,
+            &[
+                (
+                    "4882 6402 5885 aec3 771a 4b61 96d0 7abe 9410 54d4 44a8 2005 9504 0b81 66e0
+                     82a6 2d1b ff6e 919d 29ad 1718 63c7 8f0b 97c8 e9ae 82ae 43d3",
+                    RESPONSE_FIELDS[0],
+                    RESPONSE_TABLES[0],
+                ),
+                (
+                    "4883 640e ffc1 c0bf",
+                    RESPONSE_FIELDS[1],
+                    RESPONSE_TABLES[1],
+                ),
+                (
+                    "88c1 6196 d07a be94 1054 d444 a820 0595 040b 8166 e084 a62d 1bff c05a 839b
+                     d9ab 77ad 94e7 821d d7f2 e6c7 b335 dfdf cd5b 3960 d5af 2708 7f36 72c1 ab27
+                     0fb5 291f 9587 3160 65c0 03ed 4ee5 b106 3d50 07",
+                    RESPONSE_FIELDS[2],
+                    RESPONSE_TABLES[2],
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn applies_table_size_updates_at_the_start_of_a_block() {
+        let mut decoder = Decoder::new(DEFAULT_TABLE_SIZE, MAX_HEADER_LIST_SIZE);
+        decoder
+            .decode(&hex("8286 8441 0f77 7777 2e65 7861 6d70 6c65 2e63 6f6d"))
+            .unwrap();
+
+        // Shrinking the table to zero evicts every entry
+        assert_eq!(
+            decoder.decode(&hex("2082")).unwrap(),
+            fields(&[(":method", "GET")])
+        );
+        assert!(table(&decoder).is_empty());
+
+        assert!(matches!(
+            decoder.decode(&hex("8220")),
+            Err(DecodeError::UnexpectedTableSizeUpdate)
+        ));
+        assert!(matches!(
+            decoder.decode(&hex("3fe2 1f")),
+            Err(DecodeError::InvalidTableSize(4097))
+        ));
+    }
+
+    #[test]
+    fn rejects_invalid_indices() {
+        let mut decoder = Decoder::new(DEFAULT_TABLE_SIZE, MAX_HEADER_LIST_SIZE);
+
+        assert!(matches!(
+            decoder.decode(&hex("be")),
+            Err(DecodeError::InvalidIndex(62))
+        ));
+        assert!(matches!(
+            decoder.decode(&hex("80")),
+            Err(DecodeError::InvalidIndex(0))
+        ));
+    }
+
+    #[test]
+    fn rejects_truncated_blocks() {
+        let mut decoder = Decoder::new(DEFAULT_TABLE_SIZE, MAX_HEADER_LIST_SIZE);
+
+        assert!(matches!(
+            decoder.decode(&hex("400a 6375 7374 6f6d")),
+            Err(DecodeError::UnexpectedEnd)
+        ));
+    }
+
+    #[test]
+    fn limits_the_decoded_header_list_size() {
+        // Every `:method: GET` counts 3 + 7 + 32 bytes, the limit allows two of them
+        let mut decoder = Decoder::new(DEFAULT_TABLE_SIZE, 100);
+
+        assert_eq!(decoder.decode(&hex("8282")).unwrap().len(), 2);
+        assert!(matches!(
+            decoder.decode(&hex("8282 82")),
+            Err(DecodeError::HeaderListTooLarge(100))
+        ));
+    }
+
+    #[test]
+    fn keeps_the_table_in_sync_past_the_limit() {
+        let mut decoder = Decoder::new(DEFAULT_TABLE_SIZE, 100);
+
+        // The entry added after the limit was crossed is still added to the table
+        assert!(matches!(
+            decoder.decode(&hex(
+                "8282 400a 6375 7374 6f6d 2d6b 6579 0d63 7573 746f 6d2d 6865 6164 6572"
+            )),
+            Err(DecodeError::HeaderListTooLarge(100))
+        ));
+        assert_eq!(
+            decoder.decode(&hex("be")).unwrap(),
+            fields(&[("custom-key", "custom-header")])
+        );
+    }
+
+    #[test]
+    fn encoded_blocks_decode_to_the_same_fields() {
+        let expected = fields(&[
+            (":status", "200"),
+            (":status", "418"),
+            ("content-type", "text/plain"),
+            ("x-request-id", "abc"),
+            ("set-cookie", "a=b; Path=/"),
+        ]);
+
+        let block = encode(
+            expected
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_str())),
+        );
+        let mut decoder = Decoder::new(DEFAULT_TABLE_SIZE, MAX_HEADER_LIST_SIZE);
+
+        assert_eq!(decoder.decode(&block).unwrap(), expected);
+        assert!(table(&decoder).is_empty());
+    }
+}
@@ -0,0 +1,179 @@
+use std::sync::LazyLock;
+
+use thiserror::Error;
+
+const MAX_CODE_LENGTH: usize = 30;
+
+// Code lengths of the Huffman code from RFC 7541 Appendix B, indexed by symbol. The code is
+// canonical, so the codes themselves follow from the lengths.
+const CODE_LENGTHS: [u8; 257] = [
+    13, 23, 28, 28, 28, 28, 28, 28, 28, 24, 30, 28, 28, 30, 28, 28, //
+    28, 28, 28, 28, 28, 28, 30, 28, 28, 28, 28, 28, 28, 28, 28, 28, //
+    6, 10, 10, 12, 13, 6, 8, 11, 10, 10, 8, 11, 8, 6, 6, 6, //
+    5, 5, 5, 6, 6, 6, 6, 6, 6, 6, 7, 8, 15, 6, 12, 10, //
+    13, 6, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, //
+    7, 7, 7, 7, 7, 7, 7, 7, 8, 7, 8, 13, 19, 13, 14, 6, //
+    15, 5, 6, 5, 6, 5, 6, 6, 6, 5, 7, 7, 6, 6, 6, 5, //
+    6, 7, 6, 5, 5, 6, 7, 7, 7, 7, 7, 15, 11, 14, 13, 28, //
+    20, 22, 20, 20, 22, 22, 22, 23, 22, 23, 23, 23, 23, 23, 24, 23, //
+    24, 24, 22, 23, 24, 23, 23, 23, 23, 21, 22, 23, 22, 23, 23, 24, //
+    22, 21, 20, 22, 22, 23, 23, 21, 23, 22, 22, 24, 21, 22, 23, 23, //
+    21, 21, 22, 21, 23, 22, 23, 23, 20, 22, 22, 22, 23, 22, 22, 23, //
+    26, 26, 20, 19, 22, 23, 22, 25, 26, 26, 26, 27, 27, 26, 24, 25, //
+    19, 21, 26, 27, 27, 26, 27, 24, 21, 21, 26, 26, 28, 27, 27, 27, //
+    20, 24, 20, 21, 22, 21, 21, 23, 22, 22, 25, 25, 24, 24, 26, 23, //
+    26, 27, 26, 26, 27, 27, 27, 27, 27, 28, 27, 27, 27, 27, 27, 26, //
+    30,
+];
+
+/// Symbols ordered by code, together with the number of codes of each length
+struct CanonicalCode {
+    symbols: Vec<usize>,
+    counts: [u32; MAX_CODE_LENGTH + 1],
+}
+
+static CANONICAL_CODE: LazyLock<CanonicalCode> = LazyLock::new(|| {
+    let mut symbols = (0..CODE_LENGTHS.len()).collect::<Vec<_>>();
+    symbols.sort_by_key(|&symbol| (CODE_LENGTHS[symbol], symbol));
+
+    let mut counts = [0; MAX_CODE_LENGTH + 1];
+    for &length in &CODE_LENGTHS {
+        counts[usize::from(length)] += 1;
+    }
+
+    CanonicalCode { symbols, counts }
+});
+
+#[derive(Error, Debug)]
+pub enum DecodeError {
+    #[error("Huffman string contains the EOS symbol")]
+    UnexpectedEos,
+
+    #[error("Huffman string has invalid padding")]
+    InvalidPadding,
+}
+
+/// Decodes a Huffman encoded string literal, see RFC 7541 section 5.2
+pub fn decode(data: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    let CanonicalCode { symbols, counts } = &*CANONICAL_CODE;
+
+    let mut decoded = Vec::with_capacity(data.len() * 8 / 5);
+
+    let mut code = 0u32;
+    let mut length = 0;
+    // First code and index into `symbols` of the codes with the current length
+    let mut first_code = 0u32;
+    let mut first_index = 0u32;
+
+    for byte in data {
+        for shift in (0..8).rev() {
+            code = (code << 1) | u32::from((byte >> shift) & 1);
+            length += 1;
+
+            if code - first_code < counts[length] {
+                // The only symbol that doesn't fit in a byte is EOS
+                let Ok(symbol) = u8::try_from(symbols[(first_index + code - first_code) as usize])
+                else {
+                    return Err(DecodeError::UnexpectedEos);
+                };
+
+                decoded.push(symbol);
+
+                code = 0;
+                length = 0;
+                first_code = 0;
+                first_index = 0;
+            } else {
+                first_index += counts[length];
+                first_code = (first_code + counts[length]) << 1;
+
+                if length == MAX_CODE_LENGTH {
+                    return Err(DecodeError::UnexpectedEos);
+                }
+            }
+        }
+    }
+
+    // Leftover bits must be a prefix of EOS, i.e. fewer than 8 bits that are all set
+    if length >= 8 || code != (1 << length) - 1 {
+        return Err(DecodeError::InvalidPadding);
+    }
+
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(encoded: &str) -> Vec<u8> {
+        let digits = encoded
+            .chars()
+            .filter(|char| !char.is_whitespace())
+            .collect::<Vec<_>>();
+
+        digits
+            .chunks(2)
+            .map(|pair| u8::from_str_radix(&pair.iter().collect::<String>(), 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn decodes_strings_of_rfc_examples() {
+        // String literals of RFC 7541 Appendix C.4 and C.6
+        for (encoded, decoded) in [
+            ("f1e3 c2e5 f23a 6ba0 ab90 f4ff", "www.example.com"),
+            ("a8eb 1064 9cbf", "no-cache"),
+            ("25a8 49e9 5ba9 7d7f", "custom-key"),
+            ("25a8 49e9 5bb8 e8b4 bf", "custom-value"),
+            ("6402", "302"),
+            ("aec3 771a 4b", "private"),
+            (
+                "d07a be94 1054 d444 a820 0595 040b 8166 e082 a62d 1bff",
+                "Mon, 21 Oct 2013 20:13:21 GMT",
+            ),
+            (
+                "9d29 ad17 1863 c78f 0b97 c8e9 ae82 ae43 d3",
+                "https://www.example.com",
+            ),
+            ("9bd9 ab", "gzip"),
+        ] {
+            assert_eq!(decode(&hex(encoded)).unwrap(), decoded.as_bytes());
+        }
+    }
+
+    #[test]
+    fn decodes_empty_strings() {
+        assert!(decode(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn decodes_symbols_with_the_longest_codes() {
+        // 0xfe has a 27 bit code, 0x0a a 30 bit code, both padded with ones
+        assert_eq!(decode(&hex("ffff fe1f")).unwrap(), [0xfe]);
+        assert_eq!(decode(&hex("ffff fff3")).unwrap(), [0x0a]);
+    }
+
+    #[test]
+    fn rejects_padding_that_is_not_a_prefix_of_eos() {
+        // `0` is 00000, followed by three zero bits instead of ones
+        assert!(matches!(decode(&[0x00]), Err(DecodeError::InvalidPadding)));
+    }
+
+    #[test]
+    fn rejects_padding_longer_than_seven_bits() {
+        // `0` followed by a whole byte of ones
+        assert!(matches!(
+            decode(&[0x07, 0xff]),
+            Err(DecodeError::InvalidPadding)
+        ));
+    }
+
+    #[test]
+    fn rejects_eos() {
+        assert!(matches!(
+            decode(&hex("ffff fffc")),
+            Err(DecodeError::UnexpectedEos)
+        ));
+    }
+}
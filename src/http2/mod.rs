@@ -0,0 +1,140 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use thiserror::Error;
+
+use crate::types::{
+    body::Body,
+    header::{TransferEncoding, CONNECTION_HEADER_NAME},
+    request::RequestMessage,
+    request_line::{HttpVersion, HttpVersionEnum},
+    response::ResponseMessage,
+    response_header::ResponseHeader,
+    response_line::ResponseLine,
+    status::Status,
+};
+
+mod connection;
+pub mod frame;
+pub mod hpack;
+mod huffman;
+
+pub use connection::{serve, Handshake};
+
+pub const PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+// The preface is shaped like a request line so that HTTP/1.1 parsers stop at it
+pub const PREFACE_REQUEST_LINE: &str = "PRI * HTTP/2.0";
+const UPGRADE_TOKEN: &str = "h2c";
+const UPGRADE_HEADER_NAME: &str = "upgrade";
+const SETTINGS_HEADER_NAME: &str = "http2-settings";
+
+#[derive(Error, Debug)]
+pub enum ConnectionError {
+    #[error("Failed to read from or write to connection: {0:?}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Invalid connection preface")]
+    InvalidPreface,
+
+    #[error("Frame parse error: {0:?}")]
+    FrameParseError(#[from] frame::ParseError),
+
+    #[error("Header block decode error: {0:?}")]
+    CompressionError(#[from] hpack::DecodeError),
+
+    #[error("Protocol error: {0}")]
+    ProtocolError(String),
+
+    #[error("Flow control error: {0}")]
+    FlowControlError(String),
+
+    #[error("Header block exceeds the limit of {0} bytes")]
+    HeaderBlockTooLarge(usize),
+
+    #[error("Connection preface was not received in time")]
+    PrefaceTimeout,
+
+    #[error("Header block of stream {0} was not received in time")]
+    HeaderBlockTimeout(u32),
+}
+
+impl ConnectionError {
+    /// Code of the GOAWAY frame that should be sent to the peer, if the connection is still usable
+    /// for writing one
+    pub const fn error_code(&self) -> Option<frame::ErrorCode> {
+        Some(match self {
+            Self::IoError(_) | Self::FrameParseError(frame::ParseError::ReadError(_)) => {
+                return None
+            }
+            Self::FrameParseError(frame::ParseError::InvalidFrameSize { .. }) => {
+                frame::ErrorCode::FRAME_SIZE_ERROR
+            }
+            Self::CompressionError(_) => frame::ErrorCode::COMPRESSION_ERROR,
+            Self::FlowControlError(_) => frame::ErrorCode::FLOW_CONTROL_ERROR,
+            // Clients this slow are holding on to the connection rather than using it
+            Self::HeaderBlockTooLarge(_) | Self::PrefaceTimeout | Self::HeaderBlockTimeout(_) => {
+                frame::ErrorCode::ENHANCE_YOUR_CALM
+            }
+            Self::InvalidPreface | Self::FrameParseError(_) | Self::ProtocolError(_) => {
+                frame::ErrorCode::PROTOCOL_ERROR
+            }
+        })
+    }
+}
+
+/// Settings of the client if the request asks to switch to HTTP/2 over cleartext, see RFC 7540
+/// section 3.2
+///
+/// Requests with a body are answered over HTTP/1.1 instead, as the upgrade would have to wait for
+/// the whole body to arrive.
+pub fn upgrade_settings(request_message: &RequestMessage) -> Option<Vec<(frame::SettingId, u32)>> {
+    if request_message.request_line.http_version != HttpVersion::new(HttpVersionEnum::V1_1) {
+        return None;
+    }
+
+    let header = &request_message.header;
+
+    let has_token = |name: &str, token: &str| {
+        header
+            .other_headers
+            .get_all(name)
+            .flat_map(|value| value.split(','))
+            .any(|option| option.trim().eq_ignore_ascii_case(token))
+    };
+
+    if !has_token(UPGRADE_HEADER_NAME, UPGRADE_TOKEN)
+        || !has_token(CONNECTION_HEADER_NAME, UPGRADE_HEADER_NAME)
+        || !has_token(CONNECTION_HEADER_NAME, SETTINGS_HEADER_NAME)
+        || header.transfer_encoding == Some(TransferEncoding::Chunked)
+        || header.content_length.get() != 0
+    {
+        return None;
+    }
+
+    let mut settings = header.other_headers.get_all(SETTINGS_HEADER_NAME);
+    let (Some(settings), None) = (settings.next(), settings.next()) else {
+        return None;
+    };
+
+    let payload = URL_SAFE_NO_PAD
+        .decode(settings.trim().trim_end_matches('='))
+        .ok()?;
+
+    frame::parse_settings(&payload)
+}
+
+/// Interim response accepting an h2c upgrade, after which the connection speaks HTTP/2
+pub fn switching_protocols_response() -> ResponseMessage {
+    let mut response = ResponseMessage::new(
+        ResponseLine::new(
+            HttpVersion::new(HttpVersionEnum::V1_1),
+            Status::SWITCHING_PROTOCOLS,
+        ),
+        ResponseHeader::default(),
+        Body::default(),
+    );
+
+    let other_headers = &mut response.header.other_headers;
+    other_headers.insert(CONNECTION_HEADER_NAME, "Upgrade");
+    other_headers.insert(UPGRADE_HEADER_NAME, UPGRADE_TOKEN);
+
+    response
+}
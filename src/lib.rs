@@ -0,0 +1,28 @@
+//! HTTP/1.1 and HTTP/2 server built on tokio
+//!
+//! Requests are dispatched by a [`Router`] to async handlers, and a [`Server`] serves the router
+//! on plain and TLS listeners until it's shut down.
+
+mod chunked;
+pub mod config;
+pub mod error;
+pub mod handler;
+mod http2;
+pub mod multipart;
+mod request;
+mod response;
+pub mod router;
+pub mod server;
+mod shutdown;
+pub mod tls;
+pub mod types;
+
+pub use handler::Handler;
+pub use router::Router;
+pub use server::Server;
+pub use types::{
+    body::Body,
+    request::RequestMessage,
+    response::{IntoResponse, ResponseMessage},
+    status::Status,
+};
@@ -1,11 +1,7 @@
-use tokio::net::TcpListener;
-
 use anyhow::{Context, Result};
+use http::{config::ConnectionConfig, error::ErrorFormat, tls, Router, Server};
 
 mod endpoints;
-mod request;
-mod response;
-mod types;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -20,28 +16,30 @@ async fn main() -> Result<()> {
 
     let host = std::env::var("HOST").unwrap_or_else(|_| "127.0.0.1".to_owned());
     let port = std::env::var("PORT").unwrap_or_else(|_| "8080".to_owned());
-
-    let url = format!("{host}:{port}");
-
-    let listener = TcpListener::bind(&url).await?;
-    tracing::info!("Listening on {url}");
-
-    loop {
-        let (stream, _) = match listener.accept().await {
-            Ok(conn) => conn,
-            Err(err) => {
-                tracing::error!("Failed to accept connection: {err:?}");
-                continue;
-            }
-        };
-
-        tokio::spawn(async move {
-            match response::handle(stream).await {
-                Ok(response_message) => {
-                    tracing::info!("Generated response message as {response_message:?}");
-                }
-                Err(err) => tracing::error!("Error while handling incoming stream: {err:?}"),
-            }
-        });
+    let tls_port = std::env::var("TLS_PORT").unwrap_or_else(|_| "8443".to_owned());
+
+    let config = ConnectionConfig::from_env();
+
+    let router = Router::new()
+        .error_format(ErrorFormat::from_env())
+        .get("/", endpoints::root::handle)
+        .get("/api", endpoints::api::handle)
+        .post("/api", endpoints::api::handle)
+        .get("/about", endpoints::about::handle)
+        .get("/report", endpoints::report::handle)
+        .get("/greet/:name", endpoints::greet::handle)
+        .post("/upload", endpoints::upload::handle);
+
+    let mut builder = Server::builder()
+        .bind(format!("{host}:{port}"))
+        .router(router)
+        .config(config);
+
+    if let Some(acceptor) = tls::acceptor_from_env().context("Failed to configure TLS")? {
+        builder = builder.tls(format!("{host}:{tls_port}"), acceptor);
     }
+
+    builder.build().await?.run().await?;
+
+    Ok(())
 }
@@ -0,0 +1,671 @@
+use std::path::Path;
+
+use thiserror::Error;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::types::{
+    body::BodyStream,
+    header::{self, ContentDisposition, ContentType},
+    header_map::HeaderMap,
+};
+
+const CRLF: &[u8] = b"\r\n";
+const HEADER_SECTION_END: &[u8] = b"\r\n\r\n";
+const DELIMITER_PREFIX: &[u8] = b"\r\n--";
+const CLOSE_DELIMITER_SUFFIX: &[u8] = b"--";
+const TRANSPORT_PADDING: &[u8] = b" \t";
+// Boundaries are 1 to 70 characters long, see RFC 2046 section 5.1.1
+const MAX_BOUNDARY_LENGTH: usize = 70;
+const MAX_PART_HEADER_SIZE: usize = 8 * 1024;
+
+const DEFAULT_MAX_PARTS: usize = 1000;
+const DEFAULT_MAX_PART_SIZE: u64 = 16 * 1024 * 1024;
+const DEFAULT_MAX_TOTAL_SIZE: u64 = 64 * 1024 * 1024;
+
+#[derive(Error, Debug)]
+pub enum ParseError {
+    #[error("Failed to read multipart body: {0:?}")]
+    ReadError(#[from] std::io::Error),
+
+    #[error("Content type is not multipart: {0}")]
+    NotMultipart(String),
+
+    #[error("Missing or invalid boundary")]
+    InvalidBoundary,
+
+    #[error("Boundary is followed by unexpected data")]
+    InvalidDelimiter,
+
+    #[error("Multipart body ended before its close delimiter")]
+    UnexpectedEof,
+
+    #[error("Invalid part header: {0}")]
+    InvalidPartHeader(String),
+
+    #[error("Part header exceeds the limit of {0} bytes")]
+    PartHeaderTooLarge(usize),
+
+    #[error("Body exceeds the limit of {0} parts")]
+    TooManyParts(usize),
+
+    #[error("Part exceeds the limit of {0} bytes")]
+    PartTooLarge(u64),
+
+    #[error("Multipart body exceeds the limit of {0} bytes")]
+    BodyTooLarge(u64),
+
+    #[error("Header parse error: {0:?}")]
+    InvalidHeader(#[from] header::ParseError),
+
+    #[error("Part is not valid UTF-8: {0:?}")]
+    InvalidText(#[from] std::string::FromUtf8Error),
+}
+
+/// Sizes the body may not exceed, reading fails as soon as one is crossed
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    pub max_parts: usize,
+    pub max_part_size: u64,
+    pub max_total_size: u64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_parts: DEFAULT_MAX_PARTS,
+            max_part_size: DEFAULT_MAX_PART_SIZE,
+            max_total_size: DEFAULT_MAX_TOTAL_SIZE,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Preamble,
+    // The buffer starts with a delimiter
+    Delimiter,
+    Part,
+    Done,
+}
+
+/// Reads the parts of a multipart body one after the other as described in RFC 2046 section 5.1,
+/// holding no more than a chunk of the body in memory at a time
+#[derive(Debug)]
+pub struct Multipart {
+    source: BodyStream,
+    // The CRLF in front of the boundary belongs to the delimiter rather than to the part before it
+    delimiter: Vec<u8>,
+    buffer: Vec<u8>,
+    state: State,
+    limits: Limits,
+    total_size: u64,
+    parts: usize,
+    part_size: u64,
+}
+
+impl Multipart {
+    /// # Errors
+    ///
+    /// Returns an error if the content type is not multipart or lacks a valid boundary
+    pub fn new(
+        source: BodyStream,
+        content_type: &ContentType,
+        limits: Limits,
+    ) -> Result<Self, ParseError> {
+        if !content_type.is_multipart() {
+            return Err(ParseError::NotMultipart(
+                content_type.mime_type().to_owned(),
+            ));
+        }
+
+        let boundary = content_type
+            .boundary()
+            .filter(|boundary| (1..=MAX_BOUNDARY_LENGTH).contains(&boundary.len()))
+            .ok_or(ParseError::InvalidBoundary)?;
+
+        Ok(Self {
+            source,
+            delimiter: [DELIMITER_PREFIX, boundary.as_bytes()].concat(),
+            // The first delimiter may open the body without a CRLF in front of it
+            buffer: CRLF.to_vec(),
+            state: State::Preamble,
+            limits,
+            total_size: 0,
+            parts: 0,
+            part_size: 0,
+        })
+    }
+
+    /// Next part of the body, whatever the caller left unread of the previous part is skipped
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the body is malformed, ends early or exceeds the limits
+    pub async fn next_part(&mut self) -> Result<Option<Part<'_>>, ParseError> {
+        loop {
+            match self.state {
+                State::Preamble => {
+                    // Anything before the first delimiter is ignored
+                    if let Some(position) = find(&self.buffer, &self.delimiter) {
+                        self.buffer.drain(..position);
+                        self.state = State::Delimiter;
+                        continue;
+                    }
+
+                    let keep = self.delimiter.len() - 1;
+                    self.buffer.drain(..self.buffer.len().saturating_sub(keep));
+                    self.fill_or_fail().await?;
+                }
+                State::Part => while self.read_part_chunk().await?.is_some() {},
+                State::Delimiter => {
+                    if self.buffer.len() < self.delimiter.len() + CLOSE_DELIMITER_SUFFIX.len() {
+                        self.fill_or_fail().await?;
+                        continue;
+                    }
+
+                    let after_delimiter = &self.buffer[self.delimiter.len()..];
+
+                    if after_delimiter.starts_with(CLOSE_DELIMITER_SUFFIX) {
+                        self.state = State::Done;
+                        // The epilogue is ignored, it's only read so the body is consumed whole
+                        self.buffer.clear();
+                        while self.fill().await? {
+                            self.buffer.clear();
+                        }
+
+                        continue;
+                    }
+
+                    // Transport padding may follow the boundary on the same line
+                    match find(after_delimiter, CRLF) {
+                        Some(position)
+                            if after_delimiter[..position]
+                                .iter()
+                                .all(|byte| TRANSPORT_PADDING.contains(byte)) =>
+                        {
+                            self.parts += 1;
+                            if self.parts > self.limits.max_parts {
+                                return Err(ParseError::TooManyParts(self.limits.max_parts));
+                            }
+
+                            self.buffer
+                                .drain(..self.delimiter.len() + position + CRLF.len());
+
+                            let headers = self.read_part_headers().await?;
+                            self.state = State::Part;
+                            self.part_size = 0;
+
+                            return Part::new(self, headers).map(Some);
+                        }
+                        Some(_) => return Err(ParseError::InvalidDelimiter),
+                        None if after_delimiter.len() > MAX_BOUNDARY_LENGTH => {
+                            return Err(ParseError::InvalidDelimiter)
+                        }
+                        None => self.fill_or_fail().await?,
+                    }
+                }
+                State::Done => return Ok(None),
+            }
+        }
+    }
+
+    async fn read_part_headers(&mut self) -> Result<HeaderMap, ParseError> {
+        // A part without any header starts with the empty line right away
+        let (section_length, consumed) = loop {
+            if self.buffer.starts_with(CRLF) {
+                break (0, CRLF.len());
+            }
+
+            if let Some(position) = find(&self.buffer, HEADER_SECTION_END) {
+                break (position, position + HEADER_SECTION_END.len());
+            }
+
+            if self.buffer.len() > MAX_PART_HEADER_SIZE {
+                return Err(ParseError::PartHeaderTooLarge(MAX_PART_HEADER_SIZE));
+            }
+
+            self.fill_or_fail().await?;
+        };
+
+        // The section may have arrived in a single chunk
+        if section_length > MAX_PART_HEADER_SIZE {
+            return Err(ParseError::PartHeaderTooLarge(MAX_PART_HEADER_SIZE));
+        }
+
+        let section = String::from_utf8_lossy(&self.buffer[..section_length]).into_owned();
+        self.buffer.drain(..consumed);
+
+        let mut headers = HeaderMap::new();
+        for line in section.split("\r\n").filter(|line| !line.is_empty()) {
+            let Some((key, value)) = line.split_once(':') else {
+                return Err(ParseError::InvalidPartHeader(line.to_owned()));
+            };
+
+            headers.append(key.trim_end(), value.trim());
+        }
+
+        Ok(headers)
+    }
+
+    async fn read_part_chunk(&mut self) -> Result<Option<Vec<u8>>, ParseError> {
+        while self.state == State::Part {
+            let (length, finished) = match find(&self.buffer, &self.delimiter) {
+                Some(position) => (position, true),
+                // A delimiter may be split across chunks, so whatever could start one is kept back
+                None => (
+                    self.buffer.len().saturating_sub(self.delimiter.len() - 1),
+                    false,
+                ),
+            };
+
+            if finished {
+                self.state = State::Delimiter;
+            }
+
+            if length > 0 {
+                self.part_size += length as u64;
+                if self.part_size > self.limits.max_part_size {
+                    return Err(ParseError::PartTooLarge(self.limits.max_part_size));
+                }
+
+                return Ok(Some(self.buffer.drain(..length).collect()));
+            }
+
+            if !finished {
+                self.fill_or_fail().await?;
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Appends the next chunk of the body to the buffer, returns false once the body is exhausted
+    async fn fill(&mut self) -> Result<bool, ParseError> {
+        let Some(chunk) = self.source.next_chunk().await? else {
+            return Ok(false);
+        };
+
+        self.total_size += chunk.len() as u64;
+        if self.total_size > self.limits.max_total_size {
+            return Err(ParseError::BodyTooLarge(self.limits.max_total_size));
+        }
+
+        self.buffer.extend_from_slice(&chunk);
+        Ok(true)
+    }
+
+    async fn fill_or_fail(&mut self) -> Result<(), ParseError> {
+        if self.fill().await? {
+            Ok(())
+        } else {
+            Err(ParseError::UnexpectedEof)
+        }
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Part of a multipart body, its data is read chunk by chunk as it arrives
+#[derive(Debug)]
+pub struct Part<'a> {
+    multipart: &'a mut Multipart,
+    headers: HeaderMap,
+    content_disposition: Option<ContentDisposition>,
+    content_type: ContentType,
+}
+
+impl<'a> Part<'a> {
+    fn new(multipart: &'a mut Multipart, headers: HeaderMap) -> Result<Self, ParseError> {
+        let content_disposition = headers.get_typed::<ContentDisposition>().transpose()?;
+        // Parts without a type are plain text, see RFC 7578 section 4.4
+        let content_type = headers
+            .get_typed::<ContentType>()
+            .transpose()?
+            .unwrap_or(ContentType::TEXT_PLAIN);
+
+        Ok(Self {
+            multipart,
+            headers,
+            content_disposition,
+            content_type,
+        })
+    }
+
+    pub const fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Name of the form field, see RFC 7578 section 4.2
+    pub fn name(&self) -> Option<&str> {
+        self.content_disposition
+            .as_ref()
+            .and_then(ContentDisposition::name)
+    }
+
+    /// File name as sent by the client, which must not be trusted as a path
+    pub fn filename(&self) -> Option<&str> {
+        self.content_disposition
+            .as_ref()
+            .and_then(ContentDisposition::filename)
+    }
+
+    pub const fn content_type(&self) -> &ContentType {
+        &self.content_type
+    }
+
+    /// Returns the next non-empty chunk of the part or `None` once the part is exhausted
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the body can't be read or the part exceeds the limits
+    pub async fn next_chunk(&mut self) -> Result<Option<Vec<u8>>, ParseError> {
+        self.multipart.read_part_chunk().await
+    }
+
+    /// Collects the rest of the part in memory, which is meant for small parts such as form fields
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the body can't be read or the part exceeds the limits
+    pub async fn bytes(&mut self) -> Result<Vec<u8>, ParseError> {
+        let mut data = Vec::new();
+        while let Some(chunk) = self.next_chunk().await? {
+            data.extend_from_slice(&chunk);
+        }
+
+        Ok(data)
+    }
+
+    /// # Errors
+    ///
+    /// Returns an error if the part can't be read or is not valid UTF-8
+    pub async fn text(&mut self) -> Result<String, ParseError> {
+        Ok(String::from_utf8(self.bytes().await?)?)
+    }
+
+    /// Streams the rest of the part into the sink, returns the number of bytes written
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the part can't be read or the sink can't be written to
+    pub async fn copy_to<W: AsyncWrite + Unpin>(
+        &mut self,
+        sink: &mut W,
+    ) -> Result<u64, ParseError> {
+        let mut written = 0;
+        while let Some(chunk) = self.next_chunk().await? {
+            sink.write_all(&chunk).await?;
+            written += chunk.len() as u64;
+        }
+
+        sink.flush().await?;
+        Ok(written)
+    }
+
+    /// Streams the rest of the part into a new file, which is removed again if the part can't be
+    /// read whole
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the part can't be read or the file can't be written
+    pub async fn save_to(&mut self, path: impl AsRef<Path>) -> Result<u64, ParseError> {
+        let path = path.as_ref();
+
+        let mut file = tokio::fs::File::create(path).await?;
+        let result = self.copy_to(&mut file).await;
+
+        if result.is_err() {
+            if let Err(err) = tokio::fs::remove_file(path).await {
+                tracing::debug!("Failed to remove incomplete upload {path:?}: {err:?}");
+            }
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BODY: &[u8] = b"--xyz\r\n\
+        Content-Disposition: form-data; name=\"field\"\r\n\
+        \r\n\
+        value\r\n\
+        --xyz\r\n\
+        Content-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\n\
+        Content-Type: application/octet-stream\r\n\
+        \r\n\
+        line one\r\nline two\r\n\
+        --xyz--\r\n";
+
+    fn multipart(chunks: &[&[u8]], limits: Limits) -> Multipart {
+        let (sender, body_stream) = BodyStream::channel(chunks.len().max(1));
+        for chunk in chunks {
+            sender.try_send(chunk.to_vec()).unwrap();
+        }
+
+        let content_type = "multipart/form-data; boundary=xyz".parse().unwrap();
+        Multipart::new(body_stream, &content_type, limits).unwrap()
+    }
+
+    // Name, content type and data of every part
+    async fn read_all(
+        chunks: &[&[u8]],
+        limits: Limits,
+    ) -> Result<Vec<(Option<String>, String, Vec<u8>)>, ParseError> {
+        let mut multipart = multipart(chunks, limits);
+        let mut parts = Vec::new();
+
+        while let Some(mut part) = multipart.next_part().await? {
+            let name = part.name().map(ToOwned::to_owned);
+            let content_type = part.content_type().mime_type().to_owned();
+            parts.push((name, content_type, part.bytes().await?));
+        }
+
+        Ok(parts)
+    }
+
+    fn expected_parts() -> Vec<(Option<String>, String, Vec<u8>)> {
+        vec![
+            (
+                Some("field".to_owned()),
+                "text/plain".to_owned(),
+                b"value".to_vec(),
+            ),
+            (
+                Some("file".to_owned()),
+                "application/octet-stream".to_owned(),
+                b"line one\r\nline two".to_vec(),
+            ),
+        ]
+    }
+
+    #[tokio::test]
+    async fn reads_parts_with_their_headers() {
+        let mut multipart = multipart(&[BODY], Limits::default());
+
+        let part = multipart.next_part().await.unwrap().unwrap();
+        assert_eq!(part.name(), Some("field"));
+        assert_eq!(part.filename(), None);
+
+        // The unread data of a part is skipped
+        let part = multipart.next_part().await.unwrap().unwrap();
+        assert_eq!(part.filename(), Some("a.txt"));
+
+        assert!(multipart.next_part().await.unwrap().is_none());
+        assert_eq!(
+            read_all(&[BODY], Limits::default()).await.unwrap(),
+            expected_parts()
+        );
+    }
+
+    #[tokio::test]
+    async fn finds_delimiters_split_across_chunks() {
+        let chunks: Vec<&[u8]> = BODY.chunks(1).collect();
+        assert_eq!(
+            read_all(&chunks, Limits::default()).await.unwrap(),
+            expected_parts()
+        );
+
+        // Split inside the close delimiter
+        let split = BODY.len() - 5;
+        assert_eq!(
+            read_all(&[&BODY[..split], &BODY[split..]], Limits::default())
+                .await
+                .unwrap(),
+            expected_parts()
+        );
+    }
+
+    #[tokio::test]
+    async fn ignores_preamble_and_epilogue() {
+        let body = [
+            &b"This is the preamble, --xy is not a delimiter\r\n"[..],
+            BODY,
+            b"This is the epilogue\r\n--xyz\r\n",
+        ]
+        .concat();
+
+        assert_eq!(
+            read_all(&[&body], Limits::default()).await.unwrap(),
+            expected_parts()
+        );
+    }
+
+    #[tokio::test]
+    async fn accepts_transport_padding_after_the_boundary() {
+        let body = b"--xyz \t \r\n\r\nvalue\r\n--xyz\t\r\n\r\nother\r\n--xyz--";
+        let parts = read_all(&[body], Limits::default()).await.unwrap();
+
+        let data: Vec<_> = parts.into_iter().map(|(_, _, data)| data).collect();
+        assert_eq!(data, [b"value".to_vec(), b"other".to_vec()]);
+    }
+
+    #[tokio::test]
+    async fn accepts_a_close_delimiter_without_crlf() {
+        let body = b"--xyz\r\n\r\nvalue\r\n--xyz--";
+        let parts = read_all(&[body], Limits::default()).await.unwrap();
+
+        assert_eq!(parts.len(), 1);
+        assert_eq!(parts[0].2, b"value");
+    }
+
+    #[tokio::test]
+    async fn rejects_data_after_the_boundary() {
+        for body in [
+            &b"--xyz\r\n\r\nvalue\r\n--xyzabc\r\n\r\n--xyz--"[..],
+            b"--xyz \tx\r\n\r\n--xyz--",
+        ] {
+            assert!(
+                matches!(
+                    read_all(&[body], Limits::default()).await,
+                    Err(ParseError::InvalidDelimiter)
+                ),
+                "{body:?}"
+            );
+        }
+
+        // A delimiter line that never ends is not buffered without bound
+        let body = [&b"--xyz"[..], &[b' '; MAX_BOUNDARY_LENGTH + 1]].concat();
+        assert!(matches!(
+            read_all(&[&body], Limits::default()).await,
+            Err(ParseError::InvalidDelimiter)
+        ));
+    }
+
+    #[tokio::test]
+    async fn rejects_bodies_that_end_early() {
+        for body in [
+            &b""[..],
+            b"preamble only",
+            b"--xyz\r\nContent-Type: text/plain\r\n",
+            b"--xyz\r\n\r\nvalue without a close delimiter",
+        ] {
+            assert!(
+                matches!(
+                    read_all(&[body], Limits::default()).await,
+                    Err(ParseError::UnexpectedEof)
+                ),
+                "{body:?}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_malformed_part_headers() {
+        assert!(matches!(
+            read_all(&[b"--xyz\r\nno colon\r\n\r\n\r\n--xyz--"], Limits::default()).await,
+            Err(ParseError::InvalidPartHeader(line)) if line == "no colon"
+        ));
+    }
+
+    #[tokio::test]
+    async fn enforces_the_limits() {
+        let header = [
+            &b"--xyz\r\nX-Padding: "[..],
+            &[b'a'; MAX_PART_HEADER_SIZE],
+            b"\r\n\r\n\r\n--xyz--",
+        ]
+        .concat();
+        assert!(matches!(
+            read_all(&[&header], Limits::default()).await,
+            Err(ParseError::PartHeaderTooLarge(MAX_PART_HEADER_SIZE))
+        ));
+
+        let limits = Limits {
+            max_parts: 1,
+            ..Limits::default()
+        };
+        assert!(matches!(
+            read_all(&[BODY], limits).await,
+            Err(ParseError::TooManyParts(1))
+        ));
+
+        // The second part is the first to exceed 16 bytes
+        let limits = Limits {
+            max_part_size: 16,
+            ..Limits::default()
+        };
+        assert!(matches!(
+            read_all(&[BODY], limits).await,
+            Err(ParseError::PartTooLarge(16))
+        ));
+
+        let limits = Limits {
+            max_total_size: 64,
+            ..Limits::default()
+        };
+        assert!(matches!(
+            read_all(&[&BODY[..64], &BODY[64..]], limits).await,
+            Err(ParseError::BodyTooLarge(64))
+        ));
+    }
+
+    #[test]
+    fn requires_a_multipart_type_with_a_valid_boundary() {
+        for (content_type, valid) in [
+            ("multipart/form-data; boundary=xyz", true),
+            ("multipart/mixed; boundary=\"a b\"", true),
+            ("text/plain; boundary=xyz", false),
+            ("multipart/form-data", false),
+            ("multipart/form-data; boundary=\"\"", false),
+        ] {
+            let content_type = content_type.parse().unwrap();
+            let result = Multipart::new(BodyStream::channel(1).1, &content_type, Limits::default());
+            assert_eq!(result.is_ok(), valid, "{content_type}");
+        }
+
+        let content_type = format!("multipart/form-data; boundary={}", "a".repeat(71))
+            .parse()
+            .unwrap();
+        assert!(matches!(
+            Multipart::new(BodyStream::channel(1).1, &content_type, Limits::default()),
+            Err(ParseError::InvalidBoundary)
+        ));
+    }
+}
@@ -1,12 +1,21 @@
-use std::collections::HashMap;
+use std::{future::Future, time::Duration};
 
 use thiserror::Error;
 use tokio::{
     io::{AsyncBufReadExt, AsyncReadExt, BufReader},
-    net::TcpStream,
+    sync::mpsc,
 };
 
-use crate::types::{body, header, request, request_line};
+use crate::{
+    chunked,
+    config::ConnectionConfig,
+    http2,
+    response::{self, Transport},
+    types::{
+        body, header, header_map::HeaderMap, request, request_line, response::ResponseMessage,
+        status::Status,
+    },
+};
 
 #[derive(Error, Debug)]
 pub enum RequestMessageError {
@@ -31,59 +40,623 @@ pub enum RequestMessageError {
     #[error("Body parse error: {0:?}")]
     BodyParseError(#[from] body::ParseError),
 
-    #[error("Unknown route: {0:?}")]
-    UnknownRoute(String),
+    #[error("Chunked body parse error: {0:?}")]
+    ChunkedParseError(#[from] chunked::ParseError),
+
+    #[error("Connection closed by peer")]
+    ConnectionClosed,
+
+    #[error("Client sent the HTTP/2 connection preface")]
+    Http2PrefaceReceived,
+
+    #[error("HTTP/2 error: {0:?}")]
+    Http2Error(#[from] http2::ConnectionError),
+
+    #[error("Request line exceeds the limit of {0} bytes")]
+    RequestLineTooLong(usize),
+
+    #[error("Request has more than {0} header fields")]
+    TooManyHeaders(usize),
+
+    #[error("Header section exceeds the limit of {0} bytes")]
+    HeaderSectionTooLarge(usize),
+
+    #[error("Body exceeds the limit of {0} bytes")]
+    BodyTooLarge(u64),
+
+    #[error("Header section was not received in time")]
+    HeaderReadTimeout,
+
+    #[error("Body was not received in time")]
+    BodyReadTimeout,
+
+    #[error("Request was refused before its body was sent")]
+    ExpectationRejected(Status),
+}
+
+impl RequestMessageError {
+    /// Status of the response that should be sent back to the client, if the connection is still
+    /// usable for writing one
+    pub fn status(&self) -> Option<Status> {
+        Some(match self {
+            Self::ReadBufferError(_)
+            | Self::ConnectionClosed
+            | Self::Http2PrefaceReceived
+            | Self::Http2Error(_)
+            | Self::ChunkedParseError(chunked::ParseError::ReadError(_)) => return None,
+            Self::RequestLineParseError(request_line::ParseError::UnsupportedHttpVersion(_)) => {
+                Status::HTTP_VERSION_NOT_SUPPORTED
+            }
+            Self::RequestLineParseError(request_line::ParseError::InvalidRequestType(_))
+            | Self::HeaderParseError(header::ParseError::UnsupportedTransferEncoding(_)) => {
+                Status::NOT_IMPLEMENTED
+            }
+            Self::HeaderParseError(header::ParseError::UnsupportedExpectation(_)) => {
+                Status::EXPECTATION_FAILED
+            }
+            Self::ExpectationRejected(status) => status.clone(),
+            Self::HeaderParseError(header::ParseError::TryFromIntError(_))
+            | Self::BodyTooLarge(_)
+            | Self::ChunkedParseError(chunked::ParseError::BodyTooLarge(_)) => {
+                Status::CONTENT_TOO_LARGE
+            }
+            Self::RequestLineTooLong(_) => Status::URI_TOO_LONG,
+            Self::TooManyHeaders(_) | Self::HeaderSectionTooLarge(_) => {
+                Status::REQUEST_HEADER_FIELDS_TOO_LARGE
+            }
+            Self::HeaderReadTimeout | Self::BodyReadTimeout => Status::REQUEST_TIMEOUT,
+            Self::RequestLineNotFound
+            | Self::MultipleRequestLines(_)
+            | Self::Utf8ConversionError(_)
+            | Self::RequestLineParseError(_)
+            | Self::HeaderParseError(_)
+            | Self::BodyParseError(_)
+            | Self::ChunkedParseError(_) => Status::BAD_REQUEST,
+        })
+    }
+
+    /// Explanation safe to send to the client, without the internals of the error
+    pub const fn detail(&self) -> Option<&'static str> {
+        Some(match self {
+            Self::ReadBufferError(_)
+            | Self::ConnectionClosed
+            | Self::Http2PrefaceReceived
+            | Self::Http2Error(_)
+            | Self::ExpectationRejected(_)
+            | Self::ChunkedParseError(chunked::ParseError::ReadError(_)) => return None,
+            Self::RequestLineNotFound => "Request line is missing",
+            Self::MultipleRequestLines(_) => "Request line is malformed",
+            Self::Utf8ConversionError(_) => "Request is not valid UTF-8",
+            Self::RequestLineParseError(err) => match err {
+                request_line::ParseError::InvalidRequestLineLength(_) => {
+                    "Request line is malformed"
+                }
+                request_line::ParseError::InvalidRequestType(_) => "Method is not supported",
+                request_line::ParseError::EmtpyPath
+                | request_line::ParseError::InvalidRequestTarget(_) => "Request target is invalid",
+                request_line::ParseError::InvalidHttpVersion(_) => "HTTP version is invalid",
+                request_line::ParseError::UnsupportedHttpVersion(_) => {
+                    "HTTP version is not supported"
+                }
+            },
+            Self::HeaderParseError(err) => match err {
+                header::ParseError::MissingHeader(_) => "Required header field is missing",
+                header::ParseError::DuplicateHeader(_) => {
+                    "Header field is repeated with different values"
+                }
+                header::ParseError::RepeatedHeader(_) => "Header field must not be repeated",
+                header::ParseError::InvalidDate(_) => "Date is invalid",
+                header::ParseError::InvalidHost(_) | header::ParseError::InvalidPort(_) => {
+                    "Host is invalid"
+                }
+                header::ParseError::ParseIntError(_) => "Numeric header field is invalid",
+                header::ParseError::TryFromIntError(_) => "Content-Length is too large",
+                header::ParseError::InvalidContentType(_) => "Content-Type is invalid",
+                header::ParseError::InvalidContentDisposition(_) => {
+                    "Content-Disposition is invalid"
+                }
+                header::ParseError::UnsupportedConnection(_) => {
+                    "Connection option is not supported"
+                }
+                header::ParseError::UnsupportedTransferEncoding(_) => {
+                    "Transfer-Encoding is not supported"
+                }
+                header::ParseError::UnsupportedExpectation(_) => "Expectation is not supported",
+                header::ParseError::ConflictingFraming => {
+                    "Content-Length and Transfer-Encoding are both present"
+                }
+                header::ParseError::TransferEncodingInHttp10 => {
+                    "Transfer-Encoding is not allowed in HTTP/1.0"
+                }
+                header::ParseError::InvalidContentLength(_) => "Content-Length is invalid",
+                header::ParseError::ObsoleteLineFolding => "Header field lines must not be folded",
+                header::ParseError::InvalidFieldLine(_) => "Header field line is malformed",
+            },
+            Self::BodyParseError(_) => "Body is malformed",
+            Self::ChunkedParseError(chunked::ParseError::BodyTooLarge(_))
+            | Self::BodyTooLarge(_) => "Body is too large",
+            Self::ChunkedParseError(chunked::ParseError::LineTooLong(_)) => {
+                "Chunk line is too long"
+            }
+            Self::ChunkedParseError(_) => "Chunked body is malformed",
+            Self::RequestLineTooLong(_) => "Request line is too long",
+            Self::TooManyHeaders(_) => "Too many header fields",
+            Self::HeaderSectionTooLarge(_) => "Header section is too large",
+            Self::HeaderReadTimeout => "Header section was not received in time",
+            Self::BodyReadTimeout => "Body was not received in time",
+        })
+    }
+}
+
+// Chunks read ahead of a handler consuming a streamed body
+pub const STREAMED_BODY_BUFFER: usize = 4;
+const STREAMED_BODY_READ_SIZE: u64 = 8 * 1024;
+
+#[derive(Debug, Clone, Copy)]
+enum BodyFraming {
+    Length(u64),
+    Chunked,
+}
+
+/// Body left on the connection so that the handler can consume it while it arrives, it has to be
+/// forwarded while the handler runs
+#[derive(Debug)]
+pub struct StreamedBody {
+    framing: BodyFraming,
+    sender: mpsc::Sender<Vec<u8>>,
+    // Bounds every single read rather than the whole body, which may take long to arrive
+    read_timeout: Duration,
+}
+
+impl StreamedBody {
+    /// Reads the body off the connection into the stream handed to the handler, returns false if
+    /// the handler stopped reading before the end, which leaves the rest of the body unread
+    pub async fn forward<S: Transport>(
+        self,
+        reader: &mut BufReader<S>,
+    ) -> Result<bool, RequestMessageError> {
+        match self.framing {
+            BodyFraming::Length(length) => self.forward_exact(reader, length).await,
+            BodyFraming::Chunked => loop {
+                let chunk_size =
+                    within(self.read_timeout, chunked::read_chunk_size(reader)).await?;
+
+                if chunk_size == 0 {
+                    // The request was handed over already, so its trailers are dropped
+                    within(self.read_timeout, chunked::read_trailers(reader)).await?;
+                    return Ok(true);
+                }
+
+                if !self.forward_exact(reader, chunk_size as u64).await? {
+                    return Ok(false);
+                }
+
+                within(self.read_timeout, chunked::read_chunk_end(reader)).await?;
+            },
+        }
+    }
+
+    async fn forward_exact<S: Transport>(
+        &self,
+        reader: &mut BufReader<S>,
+        length: u64,
+    ) -> Result<bool, RequestMessageError> {
+        let mut remaining = length;
+
+        while remaining > 0 {
+            let mut chunk = Vec::new();
+            let read = within(
+                self.read_timeout,
+                (&mut *reader)
+                    .take(remaining.min(STREAMED_BODY_READ_SIZE))
+                    .read_to_end(&mut chunk),
+            )
+            .await?;
+
+            if read == 0 {
+                return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+            }
+
+            remaining -= read as u64;
+
+            if self.sender.send(chunk).await.is_err() {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+// Body reads that take longer than the configured timeout fail with 408
+async fn within<T, E: Into<RequestMessageError>>(
+    duration: Duration,
+    future: impl Future<Output = Result<T, E>>,
+) -> Result<T, RequestMessageError> {
+    tokio::time::timeout(duration, future)
+        .await
+        .map_err(|_| RequestMessageError::BodyReadTimeout)?
+        .map_err(Into::into)
+}
+
+// Reads a line of at most `limit` bytes including its line break, lines that are longer yield
+// `None` and are left partially read
+async fn read_line<S: Transport>(
+    reader: &mut BufReader<S>,
+    limit: usize,
+) -> Result<Option<String>, RequestMessageError> {
+    let mut line = Vec::new();
+    let read = (&mut *reader)
+        .take(limit as u64 + 1)
+        .read_until(b'\n', &mut line)
+        .await?;
+
+    if read == 0 {
+        return Err(RequestMessageError::ConnectionClosed);
+    }
+
+    if read > limit {
+        return Ok(None);
+    }
+
+    Ok(Some(std::str::from_utf8(&line)?.to_owned()))
 }
 
-#[tracing::instrument(name = "parse_request")]
-pub async fn parse_request(
-    stream: &mut TcpStream,
-) -> Result<request::RequestMessage, RequestMessageError> {
-    let mut reader = BufReader::new(stream);
+struct RequestHead {
+    request_line: request_line::RequestLine,
+    header: header::Header,
+}
 
-    let mut raw_headers = HashMap::new();
+// Reads the request line and the header section, see RFC 9112 section 2.1
+async fn read_head<S: Transport>(
+    reader: &mut BufReader<S>,
+    config: &ConnectionConfig,
+) -> Result<RequestHead, RequestMessageError> {
+    let mut raw_headers = HeaderMap::new();
+    let mut header_count = 0;
+    let mut remaining_header_size = config.max_header_size;
 
-    let mut request_line = None;
+    let mut request_line: Option<request_line::RequestLine> = None;
     loop {
-        let mut line = String::new();
-        reader.read_line(&mut line).await?;
+        let limit = if request_line.is_none() {
+            config.max_request_line_length
+        } else {
+            remaining_header_size
+        };
 
-        line = line.trim().to_ascii_lowercase();
+        let Some(line) = read_line(reader, limit).await? else {
+            return Err(if request_line.is_none() {
+                RequestMessageError::RequestLineTooLong(config.max_request_line_length)
+            } else {
+                RequestMessageError::HeaderSectionTooLarge(config.max_header_size)
+            });
+        };
 
-        if line.is_empty() {
-            break;
+        if request_line.is_some() {
+            remaining_header_size -= line.len();
         }
 
-        if let Ok(parsed_request_line) = line.parse() {
+        // Only the line break is stripped, whitespace that is left decides how the line is read
+        let line = line.strip_suffix('\n').unwrap_or(&line);
+        let line = line.strip_suffix('\r').unwrap_or(line);
+
+        if line.trim().is_empty() {
+            // Stray empty lines before the request line are ignored, see RFC 9112 section 2.2
             if request_line.is_none() {
-                request_line = Some(parsed_request_line);
-            } else {
-                return Err(
-                    RequestMessageError::MultipleRequestLines(
-                        format!("Found multiple request lines\nNew: {parsed_request_line:?}\nCurrent: {request_line:?}")
-                    )
-                );
+                continue;
             }
-        } else if let Some((key, value)) = line.split_once(':') {
-            raw_headers.insert(key.trim_end().to_string(), value.trim_start().to_string());
+
+            break;
+        }
+
+        // The first line of a message is always the request line, see RFC 9112 section 2.1
+        if request_line.is_none() {
+            if line == http2::PREFACE_REQUEST_LINE {
+                return Err(RequestMessageError::Http2PrefaceReceived);
+            }
+
+            request_line = Some(line.trim().parse()?);
+            continue;
+        }
+
+        header_count += 1;
+        if header_count > config.max_header_count {
+            return Err(RequestMessageError::TooManyHeaders(config.max_header_count));
+        }
+
+        if let Ok(parsed_request_line) = line.parse::<request_line::RequestLine>() {
+            return Err(
+                RequestMessageError::MultipleRequestLines(
+                    format!("Found multiple request lines\nNew: {parsed_request_line:?}\nCurrent: {request_line:?}")
+                )
+            );
         }
+
+        let (name, value) = parse_field_line(line)?;
+        raw_headers.append(name, value);
     }
 
     let Some(request_line) = request_line else {
         return Err(RequestMessageError::RequestLineNotFound);
     };
 
-    let header = header::Header::try_from(&mut raw_headers)?;
+    // HTTP/1.0 has no transfer codings, so the framing can't be trusted, see RFC 9112 section 6.1
+    if !request_line.http_version.supports_chunked()
+        && raw_headers.contains(header::TRANSFER_ENCODING_HEADER_NAME)
+    {
+        return Err(header::ParseError::TransferEncodingInHttp10.into());
+    }
+
+    let mut header = header::Header::try_from(&mut raw_headers)?;
+
+    if header.host.is_none() && request_line.http_version.requires_host() {
+        return Err(header::ParseError::MissingHeader("Missing header: host".to_owned()).into());
+    }
+
+    // The authority of an absolute-form target takes precedence over Host, see RFC 9112 section 3.2.2
+    if let Some(authority) = request_line.uri.authority() {
+        header.host = Some(authority.parse()?);
+    }
+
+    Ok(RequestHead {
+        request_line,
+        header,
+    })
+}
+
+/// Splits a header field line into its name and value, see RFC 9112 section 5
+///
+/// Lines that could be framed differently by another recipient are rejected rather than repaired:
+/// folded lines (section 5.2), whitespace before the colon (section 5.1) and lines without one.
+fn parse_field_line(line: &str) -> Result<(&str, &str), header::ParseError> {
+    if line.starts_with([' ', '\t']) {
+        return Err(header::ParseError::ObsoleteLineFolding);
+    }
+
+    let Some((name, value)) = line.split_once(':') else {
+        return Err(header::ParseError::InvalidFieldLine(line.to_owned()));
+    };
+
+    if !request_line::is_token(name) {
+        return Err(header::ParseError::InvalidFieldLine(line.to_owned()));
+    }
+
+    // Field names are case-insensitive, values are kept exactly as sent apart from surrounding
+    // whitespace
+    Ok((name, value.trim_matches([' ', '\t'])))
+}
+
+/// Parses the next request off the connection, multipart bodies are left to be streamed to the
+/// handler rather than read upfront
+///
+/// Clients that expect `100 Continue` get it once the request passed `continue_check`, otherwise
+/// the error holds the status to refuse the request with, see RFC 9110 section 10.1.1
+#[tracing::instrument(name = "parse_request", skip(config, continue_check))]
+pub async fn parse_request<S: Transport>(
+    reader: &mut BufReader<S>,
+    config: &ConnectionConfig,
+    continue_check: impl FnOnce(&request_line::RequestLine, &header::Header) -> Result<(), Status>,
+) -> Result<(request::RequestMessage, Option<StreamedBody>), RequestMessageError> {
+    let RequestHead {
+        request_line,
+        header,
+    } = tokio::time::timeout(config.header_read_timeout, read_head(reader, config))
+        .await
+        .map_err(|_| RequestMessageError::HeaderReadTimeout)??;
+
+    let framing = if header.transfer_encoding == Some(header::TransferEncoding::Chunked) {
+        Some(BodyFraming::Chunked)
+    } else {
+        Some(header.content_length.get())
+            .filter(|length| *length > 0)
+            .map(BodyFraming::Length)
+    };
+
+    // Streamed bodies are never held in memory whole, their handler decides how much it accepts
+    let streamed = framing.is_some() && header.content_type.is_multipart();
+
+    // The length is checked before the client is asked to send the body and before anything is
+    // allocated for it
+    if !streamed && header.content_length.get() > config.max_body_size {
+        return Err(RequestMessageError::BodyTooLarge(config.max_body_size));
+    }
+
+    // Expectations of requests without a body and of HTTP/1.0 requests are ignored
+    let expectation = header
+        .expect()
+        .filter(|_| framing.is_some() && request_line.http_version.supports_interim_responses());
+    if let Some(expectation) = expectation {
+        let header::Expect::Continue = expectation?;
+
+        continue_check(&request_line, &header).map_err(RequestMessageError::ExpectationRejected)?;
+
+        response::write_interim(reader.get_mut(), ResponseMessage::interim(Status::CONTINUE))
+            .await?;
+    }
+
+    if let (Some(framing), true) = (framing, streamed) {
+        let (sender, stream) = body::BodyStream::channel(STREAMED_BODY_BUFFER);
+        let body = body::Body::new(body::BodyType::Stream(stream));
+
+        return Ok((
+            request::RequestMessage::new(request_line, header, body),
+            Some(StreamedBody {
+                framing,
+                sender,
+                read_timeout: config.body_read_timeout,
+            }),
+        ));
+    }
+
+    if header.transfer_encoding == Some(header::TransferEncoding::Chunked) {
+        let chunked_body = within(
+            config.body_read_timeout,
+            chunked::decode(reader, config.max_body_size),
+        )
+        .await?;
+        let body = body::Body::new(body::BodyType::Binary(chunked_body.data));
+
+        let mut request_message = request::RequestMessage::new(request_line, header, body);
+        request_message.trailers = chunked_body.trailers;
+
+        return Ok((request_message, None));
+    }
 
     let content_length = header.content_length.try_into()?;
 
     let body = if content_length > 0 {
         let mut body = vec![0u8; content_length];
-        reader.read_exact(&mut body).await?;
-        body::Body::parse(body, &header.content_type)?
+        within(config.body_read_timeout, reader.read_exact(&mut body)).await?;
+        body::Body::new(body::BodyType::Binary(body))
     } else {
         body::Body::default()
     };
 
-    Ok(request::RequestMessage::new(request_line, header, body))
+    Ok((
+        request::RequestMessage::new(request_line, header, body),
+        None,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncWriteExt;
+
+    use super::*;
+
+    async fn parse(raw: &[u8]) -> Result<request::RequestMessage, RequestMessageError> {
+        let (mut client, server) = tokio::io::duplex(64 * 1024);
+        client.write_all(raw).await.unwrap();
+        drop(client);
+
+        let mut reader = BufReader::new(server);
+        parse_request(&mut reader, &ConnectionConfig::default(), |_, _| Ok(()))
+            .await
+            .map(|(request_message, _)| request_message)
+    }
+
+    fn assert_bad_request(result: Result<request::RequestMessage, RequestMessageError>) {
+        let err = result.unwrap_err();
+        assert_eq!(err.status(), Some(Status::BAD_REQUEST), "{err:?}");
+    }
+
+    #[tokio::test]
+    async fn parses_header_fields() {
+        let request_message = parse(
+            b"POST /api HTTP/1.1\r\nHost: localhost\r\nX-Empty:\r\nX-Spaced: \t a  b \t\r\n\
+              Content-Length: 2\r\n\r\nok",
+        )
+        .await
+        .unwrap();
+
+        let other_headers = &request_message.header.other_headers;
+        assert_eq!(other_headers.get("x-empty"), Some(""));
+        assert_eq!(other_headers.get("x-spaced"), Some("a  b"));
+        assert_eq!(request_message.body.get_type().as_bytes(), &b"ok"[..]);
+    }
+
+    #[tokio::test]
+    async fn rejects_obsolete_line_folding() {
+        let result = parse(
+            b"POST /api HTTP/1.1\r\nHost: localhost\r\nX-Foo: a\r\n Transfer-Encoding: chunked\r\n\r\n",
+        )
+        .await;
+
+        assert!(matches!(
+            result,
+            Err(RequestMessageError::HeaderParseError(
+                header::ParseError::ObsoleteLineFolding
+            ))
+        ));
+        assert_bad_request(parse(b"GET / HTTP/1.1\r\nHost: localhost\r\n\tX-Foo: a\r\n\r\n").await);
+    }
+
+    #[tokio::test]
+    async fn rejects_whitespace_before_the_colon() {
+        for raw in [
+            &b"GET / HTTP/1.1\r\nHost : localhost\r\n\r\n"[..],
+            b"GET / HTTP/1.1\r\nHost: localhost\r\nTransfer-Encoding\t: chunked\r\n\r\n",
+        ] {
+            assert!(matches!(
+                parse(raw).await,
+                Err(RequestMessageError::HeaderParseError(
+                    header::ParseError::InvalidFieldLine(_)
+                ))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_field_names() {
+        for raw in [
+            &b"GET / HTTP/1.1\r\nHost: localhost\r\n: empty\r\n\r\n"[..],
+            b"GET / HTTP/1.1\r\nHost: localhost\r\nX(Foo): a\r\n\r\n",
+            b"GET / HTTP/1.1\r\nHost: localhost\r\nX Foo: a\r\n\r\n",
+        ] {
+            assert_bad_request(parse(raw).await);
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_lines_without_colon() {
+        assert!(matches!(
+            parse(b"GET / HTTP/1.1\r\nHost: localhost\r\nno colon here\r\n\r\n").await,
+            Err(RequestMessageError::HeaderParseError(
+                header::ParseError::InvalidFieldLine(_)
+            ))
+        ));
+    }
+
+    #[tokio::test]
+    async fn rejects_signed_content_lengths() {
+        for raw in [
+            &b"POST / HTTP/1.1\r\nHost: localhost\r\nContent-Length: +0\r\n\r\n"[..],
+            b"POST / HTTP/1.1\r\nHost: localhost\r\nContent-Length: -1\r\n\r\n",
+            b"POST / HTTP/1.1\r\nHost: localhost\r\nContent-Length: 1 2\r\n\r\n",
+        ] {
+            assert!(matches!(
+                parse(raw).await,
+                Err(RequestMessageError::HeaderParseError(
+                    header::ParseError::InvalidContentLength(_)
+                ))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn decodes_chunked_bodies() {
+        let request_message = parse(
+            b"POST / HTTP/1.1\r\nHost: localhost\r\nTransfer-Encoding: chunked\r\n\r\n\
+              3\r\nabc\r\n0\r\nX-Trailer: t\r\n\r\n",
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(request_message.body.get_type().as_bytes(), &b"abc"[..]);
+        assert_eq!(request_message.trailers.get("x-trailer"), Some("t"));
+    }
+
+    #[tokio::test]
+    async fn rejects_transfer_encoding_in_http_1_0() {
+        for raw in [
+            &b"POST / HTTP/1.0\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\n"[..],
+            b"POST / HTTP/1.0\r\nTransfer-Encoding: gzip\r\nContent-Length: 2\r\n\r\nok",
+        ] {
+            let result = parse(raw).await;
+            assert!(
+                matches!(
+                    result,
+                    Err(RequestMessageError::HeaderParseError(
+                        header::ParseError::TransferEncodingInHttp10
+                    ))
+                ),
+                "{result:?}"
+            );
+        }
+
+        let request_message = parse(b"POST / HTTP/1.0\r\nContent-Length: 2\r\n\r\nok")
+            .await
+            .unwrap();
+        assert_eq!(request_message.body.get_type().as_bytes(), &b"ok"[..]);
+    }
 }
@@ -1,24 +1,482 @@
-use tokio::{io::AsyncWriteExt, net::TcpStream};
+use std::{fmt::Debug, sync::Arc};
 
-use crate::{request, types};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
 
-#[tracing::instrument(name = "handle")]
-pub async fn handle(
-    mut stream: TcpStream,
-) -> Result<types::response::ResponseMessage, request::RequestMessageError> {
-    let request_message = request::parse_request(&mut stream).await?;
+use crate::{
+    chunked,
+    config::ConnectionConfig,
+    error, http2, request,
+    router::Router,
+    shutdown::Shutdown,
+    types::{
+        body::{BodyStream, BodyType},
+        header::{Connection, ContentLength, TransferEncoding, KEEP_ALIVE_HEADER_NAME},
+        header_map::HeaderMap,
+        request::RequestMessage,
+        request_line::{HttpVersion, RequestType},
+        response::{InterimSender, ResponseMessage},
+        response_header::{Date, Server},
+    },
+};
 
-    tracing::info!("Parsed request message: {:?}", request_message);
+/// Byte stream a connection is served over, either plain TCP or TLS
+pub trait Transport: AsyncRead + AsyncWrite + Debug + Unpin + Send + 'static {}
 
-    let response = match request_message.request_line.uri.get_path() {
-        "/" => crate::endpoints::root::handle(request_message),
-        "/api" => crate::endpoints::api::handle(request_message),
-        "/about" => crate::endpoints::about::handle(request_message).await,
-        route => return Err(request::RequestMessageError::UnknownRoute(route.to_owned())),
+impl<T: AsyncRead + AsyncWrite + Debug + Unpin + Send + 'static> Transport for T {}
+
+#[tracing::instrument(name = "handle", skip(config, router, shutdown))]
+pub async fn handle<S: Transport>(
+    stream: S,
+    secure: bool,
+    config: Arc<ConnectionConfig>,
+    router: Arc<Router>,
+    mut shutdown: Shutdown,
+) -> Result<usize, request::RequestMessageError> {
+    let mut reader = BufReader::new(stream);
+    let mut served_requests = 0;
+
+    loop {
+        if !next_request_arrived(&mut reader, &config, &mut shutdown).await? {
+            tracing::debug!("Closing idle connection after {served_requests} requests");
+            return Ok(served_requests);
+        }
+
+        let (mut request_message, streamed_body) =
+            match request::parse_request(&mut reader, &config, |request_line, header| {
+                router.check_continue(request_line, header)
+            })
+            .await
+            {
+                Ok(request_message) => request_message,
+                Err(request::RequestMessageError::ConnectionClosed) => return Ok(served_requests),
+                // Clients with prior knowledge start speaking HTTP/2 right away, see RFC 9113
+                // section 3.3. Over TLS the protocol is only ever negotiated with ALPN.
+                Err(request::RequestMessageError::Http2PrefaceReceived)
+                    if served_requests == 0 && !secure =>
+                {
+                    let handshake = http2::Handshake::PriorKnowledge;
+                    return Ok(http2::serve(reader, config, router, handshake, shutdown).await?);
+                }
+                Err(err) => {
+                    write_error_response(&mut reader, &router, &err).await?;
+                    return Err(err);
+                }
+            };
+
+        request_message.secure = secure;
+
+        tracing::info!("Parsed request message: {:?}", request_message);
+
+        // h2c is the cleartext protocol, upgrades over TLS are ignored, see RFC 9113 section 3.2
+        let settings = (!secure)
+            .then(|| http2::upgrade_settings(&request_message))
+            .flatten();
+        if let Some(settings) = settings {
+            write_response(
+                reader.get_mut(),
+                &mut http2::switching_protocols_response(),
+                false,
+            )
+            .await?;
+
+            let handshake = http2::Handshake::Upgrade {
+                request_message: Box::new(request_message),
+                settings,
+            };
+            let served_streams = http2::serve(reader, config, router, handshake, shutdown).await?;
+
+            return Ok(served_requests + served_streams);
+        }
+
+        served_requests += 1;
+
+        // HTTP/1.0 connections are only persistent when the client asks for it explicitly
+        let http_version = request_message.request_line.http_version;
+        let persistent = match request_message.header.connection() {
+            Some(Connection::Close) => false,
+            Some(Connection::KeepAlive) => true,
+            None => http_version.keeps_alive_by_default(),
+        };
+        let mut keep_alive = persistent && served_requests < config.max_requests_per_connection;
+
+        // Responses to HEAD carry the same headers as GET but never a body
+        let write_body = request_message.request_line.request_type != RequestType::Head;
+
+        // A streamed body that fails is answered in place of the handler's response
+        let (mut response, forwarded) =
+            match respond(&mut reader, &router, request_message, streamed_body).await {
+                Ok(responded) => responded,
+                Err(err) => {
+                    write_error_response(&mut reader, &router, &err).await?;
+                    return Err(err);
+                }
+            };
+
+        // A body the handler didn't read whole is left behind on the connection
+        if !forwarded {
+            keep_alive = false;
+        }
+
+        // Clients are told to go elsewhere for their next request once shutdown began
+        if shutdown.is_triggered() {
+            keep_alive = false;
+        }
+
+        let write_body = write_body && response.response_line.status.allows_content();
+
+        // Answer with the version of the client rather than the one the handler picked
+        response.response_line.http_version = http_version;
+
+        response.header.date.get_or_insert_with(Date::now);
+        response.header.server.get_or_insert_with(Server::default);
+
+        if !set_framing(&mut response, http_version) {
+            keep_alive = false;
+        }
+
+        set_connection(&mut response, keep_alive, &config, served_requests);
+
+        tracing::info!("Generated response message as {response:?}");
+
+        write_response(reader.get_mut(), &mut response, write_body).await?;
+
+        if !keep_alive {
+            return Ok(served_requests);
+        }
+    }
+}
+
+/// Answers a request that failed with the status of its error, if the connection can still take
+/// a response, and tells the client that the connection closes
+async fn write_error_response<S: Transport>(
+    reader: &mut BufReader<S>,
+    router: &Router,
+    err: &request::RequestMessageError,
+) -> std::io::Result<()> {
+    let Some(status) = err.status() else {
+        return Ok(());
     };
 
-    stream.write_all(response.to_string().as_bytes()).await?;
-    stream.flush().await?;
+    let mut response = error::error_response(status, err.detail(), router.get_error_format());
+    response.header.date = Some(Date::now());
+    response.header.server = Some(Server::default());
+    response
+        .header
+        .other_headers
+        .insert_typed(&Connection::Close);
+
+    tracing::info!("Generated error response message as {response:?}");
+
+    write_response(reader.get_mut(), &mut response, true).await
+}
+
+/// Waits for the first byte of the next request for as long as keep-alive allows, the time to
+/// receive the request itself is bounded separately. A request that already arrived is still
+/// served on shutdown.
+async fn next_request_arrived<S: Transport>(
+    reader: &mut BufReader<S>,
+    config: &ConnectionConfig,
+    shutdown: &mut Shutdown,
+) -> std::io::Result<bool> {
+    tokio::select! {
+        biased;
+        filled = tokio::time::timeout(config.keep_alive_timeout, reader.fill_buf()) => {
+            match filled {
+                Ok(Ok(buffer)) => Ok(!buffer.is_empty()),
+                Ok(Err(err)) => Err(err),
+                Err(_) => Ok(false),
+            }
+        }
+        () = shutdown.triggered() => Ok(false),
+    }
+}
+
+/// Runs the handler, returns its response and whether the body was read off the connection whole
+async fn respond<S: Transport>(
+    reader: &mut BufReader<S>,
+    router: &Router,
+    mut request_message: RequestMessage,
+    streamed_body: Option<request::StreamedBody>,
+) -> Result<(ResponseMessage, bool), request::RequestMessageError> {
+    let http_version = request_message.request_line.http_version;
+
+    let (interim, mut interim_responses) = InterimSender::channel();
+    if http_version.supports_interim_responses() {
+        request_message.interim = interim;
+    }
+
+    let Some(streamed_body) = streamed_body else {
+        let handler = router.handle(request_message);
+        tokio::pin!(handler);
+
+        // Interim responses are written as they come while the final one is pending
+        let response = loop {
+            tokio::select! {
+                response = &mut handler => break response,
+                Some(mut interim) = interim_responses.recv() => {
+                    interim.response_line.http_version = http_version;
+                    write_interim(reader.get_mut(), interim).await?;
+                }
+            }
+        };
+
+        write_pending_interims(reader, &mut interim_responses, http_version).await?;
+        return Ok((response, true));
+    };
+
+    // The handler reads the body while it's forwarded off the connection, which keeps the
+    // connection busy, so interim responses wait until the body was read
+    let (response, forwarded) = tokio::join!(
+        router.handle(request_message),
+        streamed_body.forward(reader)
+    );
+    let forwarded = forwarded?;
+
+    write_pending_interims(reader, &mut interim_responses, http_version).await?;
+    Ok((response, forwarded))
+}
+
+async fn write_pending_interims<S: Transport>(
+    reader: &mut BufReader<S>,
+    interim_responses: &mut tokio::sync::mpsc::UnboundedReceiver<ResponseMessage>,
+    http_version: HttpVersion,
+) -> std::io::Result<()> {
+    while let Ok(mut interim) = interim_responses.try_recv() {
+        interim.response_line.http_version = http_version;
+        write_interim(reader.get_mut(), interim).await?;
+    }
+
+    Ok(())
+}
+
+fn set_connection(
+    response: &mut ResponseMessage,
+    keep_alive: bool,
+    config: &ConnectionConfig,
+    served_requests: usize,
+) {
+    let other_headers = &mut response.header.other_headers;
+
+    if keep_alive {
+        other_headers.insert_typed(&Connection::KeepAlive);
+        other_headers.insert(
+            KEEP_ALIVE_HEADER_NAME,
+            format!(
+                "timeout={}, max={}",
+                config.keep_alive_timeout.as_secs(),
+                config.max_requests_per_connection - served_requests
+            ),
+        );
+    } else {
+        other_headers.insert_typed(&Connection::Close);
+    }
+}
+
+/// Picks how the end of the body is signalled, returns false if it takes closing the connection
+fn set_framing(response: &mut ResponseMessage, http_version: HttpVersion) -> bool {
+    response.header.transfer_encoding = None;
+
+    // A length would announce content that is never sent, see RFC 9110 section 8.6
+    if !response.response_line.status.allows_content() {
+        response.header.content_length = None;
+        return true;
+    }
+
+    // Recomputed even if the handler set one, a length that disagrees with the body would leave
+    // the connection out of sync
+    let BodyType::Stream(stream) = response.body.get_type() else {
+        let length = response.body.get_type().as_bytes().len() as u64;
+        response.header.content_length = Some(ContentLength::new(length));
+
+        return true;
+    };
+
+    match stream.length() {
+        Some(length) => response.header.content_length = Some(ContentLength::new(length)),
+        None if http_version.supports_chunked() => {
+            response.header.content_length = None;
+            response.header.transfer_encoding = Some(TransferEncoding::Chunked);
+        }
+        // Without chunked encoding the end of the body is signalled by closing the connection
+        None => {
+            response.header.content_length = None;
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Writes a 1xx response, which ends with its header section, see RFC 9112 section 6.3
+pub async fn write_interim<S: AsyncWrite + Unpin>(
+    stream: &mut S,
+    response: ResponseMessage,
+) -> std::io::Result<()> {
+    // Anything else would be taken for the final response and leave the connection out of sync
+    let status = &response.response_line.status;
+    if !status.is_interim() {
+        tracing::warn!(
+            "Dropping interim response with status {}",
+            status.status_code()
+        );
+        return Ok(());
+    }
+
+    let header = HeaderMap::from(&response.header);
+    let head = if header.is_empty() {
+        format!("{}\r\n\r\n", response.response_line)
+    } else {
+        format!("{}\r\n{header}\r\n\r\n", response.response_line)
+    };
+
+    stream.write_all(head.as_bytes()).await?;
+    stream.flush().await
+}
+
+async fn write_response<S: AsyncWrite + Unpin>(
+    stream: &mut S,
+    response: &mut ResponseMessage,
+    write_body: bool,
+) -> std::io::Result<()> {
+    let head = format!("{}\r\n{}\r\n\r\n", response.response_line, response.header);
+    stream.write_all(head.as_bytes()).await?;
+
+    if !write_body {
+        return stream.flush().await;
+    }
+
+    let chunked = response.header.transfer_encoding == Some(TransferEncoding::Chunked);
+    let content_length = response.header.content_length.map(ContentLength::get);
+
+    match response.body.get_type_mut() {
+        BodyType::Stream(body_stream) if chunked => chunked::encode(body_stream, stream).await?,
+        BodyType::Stream(body_stream) => match content_length {
+            Some(length) => write_exact(body_stream, stream, length).await?,
+            None => {
+                while let Some(chunk) = body_stream.next_chunk().await? {
+                    stream.write_all(&chunk).await?;
+                }
+            }
+        },
+        body_type => stream.write_all(&body_type.as_bytes()).await?,
+    }
+
+    stream.flush().await
+}
+
+/// Writes exactly the announced length of a stream, bytes beyond it are dropped since they would
+/// be read as the next response. A stream that ends early fails, the connection has to be closed
+/// so the client notices the body is incomplete.
+async fn write_exact<S: AsyncWrite + Unpin>(
+    body_stream: &mut BodyStream,
+    stream: &mut S,
+    length: u64,
+) -> std::io::Result<()> {
+    let mut remaining = length;
+
+    while remaining > 0 {
+        let Some(chunk) = body_stream.next_chunk().await? else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                format!("Body stream ended {remaining} bytes before its length of {length}"),
+            ));
+        };
+
+        let written = chunk
+            .len()
+            .min(usize::try_from(remaining).unwrap_or(usize::MAX));
+        stream.write_all(&chunk[..written]).await?;
+        remaining -= written as u64;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{body::Body, request_line::HttpVersionEnum, status::Status};
+
+    async fn stream_response(chunks: &[&[u8]], length: u64) -> ResponseMessage {
+        let (sender, body_stream) = BodyStream::channel(chunks.len().max(1));
+        for chunk in chunks {
+            sender.send(chunk.to_vec()).await.unwrap();
+        }
+
+        let body = Body::new(BodyType::Stream(body_stream.with_length(length)));
+        let mut response = ResponseMessage::builder().body(body);
+        assert!(set_framing(
+            &mut response,
+            HttpVersion::new(HttpVersionEnum::V1_1)
+        ));
+        response
+    }
+
+    fn body_of(written: &[u8]) -> &[u8] {
+        let end = written
+            .windows(4)
+            .position(|window| window == b"\r\n\r\n")
+            .unwrap();
+        &written[end + 4..]
+    }
+
+    #[tokio::test]
+    async fn writes_streams_of_the_announced_length() {
+        let mut response = stream_response(&[b"hel", b"lo"], 5).await;
+
+        let mut written = Vec::new();
+        write_response(&mut written, &mut response, true)
+            .await
+            .unwrap();
+
+        assert!(String::from_utf8_lossy(&written).contains("Content-Length: 5\r\n"));
+        assert_eq!(body_of(&written), b"hello");
+    }
+
+    #[tokio::test]
+    async fn cuts_streams_off_at_the_announced_length() {
+        let mut response = stream_response(&[b"hel", b"lo, world"], 5).await;
+
+        let mut written = Vec::new();
+        write_response(&mut written, &mut response, true)
+            .await
+            .unwrap();
+
+        assert_eq!(body_of(&written), b"hello");
+    }
+
+    #[tokio::test]
+    async fn fails_streams_shorter_than_announced() {
+        let mut response = stream_response(&[b"hello"], 10).await;
+
+        let mut written = Vec::new();
+        let err = write_response(&mut written, &mut response, true)
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+
+    #[tokio::test]
+    async fn writes_interim_responses() {
+        let mut written = Vec::new();
+        write_interim(&mut written, ResponseMessage::interim(Status::CONTINUE))
+            .await
+            .unwrap();
+
+        assert_eq!(written, b"HTTP/1.1 100 Continue\r\n\r\n");
+    }
+
+    #[tokio::test]
+    async fn drops_interim_responses_that_are_final() {
+        for status in [Status::OK, Status::SWITCHING_PROTOCOLS] {
+            let mut response = ResponseMessage::interim(Status::CONTINUE);
+            response.response_line.status = status;
+
+            let mut written = Vec::new();
+            write_interim(&mut written, response).await.unwrap();
 
-    Ok(response)
+            assert!(written.is_empty());
+        }
+    }
 }
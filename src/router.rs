@@ -0,0 +1,497 @@
+use std::{cmp::Reverse, collections::HashMap};
+
+use crate::{
+    error::{self, ErrorFormat},
+    handler::{Handler, HandlerFuture},
+    types::{
+        header::{Header, ALLOW_HEADER_NAME},
+        request::RequestMessage,
+        request_line::{RequestLine, RequestType, TargetForm},
+        response::ResponseMessage,
+        status::Status,
+    },
+};
+
+const PARAM_PREFIX: char = ':';
+const WILDCARD_PREFIX: char = '*';
+
+type BoxedContinueCheck = Box<dyn Fn(&RequestLine, &Header) -> Result<(), Status> + Send + Sync>;
+
+#[derive(Debug)]
+enum Segment {
+    Literal(String),
+    Param(String),
+    Wildcard(String),
+}
+
+#[derive(Debug)]
+struct PathPattern(Vec<Segment>);
+
+impl PathPattern {
+    fn parse(pattern: &str) -> Self {
+        // Empty segments are dropped the same way they are when request targets are normalized
+        let raw_segments = pattern
+            .split('/')
+            .filter(|raw_segment| !raw_segment.is_empty())
+            .collect::<Vec<_>>();
+
+        let segments = raw_segments
+            .iter()
+            .enumerate()
+            .map(|(index, raw_segment)| match raw_segment.chars().next() {
+                Some(PARAM_PREFIX) => {
+                    let name = &raw_segment[PARAM_PREFIX.len_utf8()..];
+                    assert!(!name.is_empty(), "Empty parameter name in route {pattern}");
+                    Segment::Param(name.to_owned())
+                }
+                Some(WILDCARD_PREFIX) => {
+                    assert!(
+                        index == raw_segments.len() - 1,
+                        "Wildcard must be the last segment in route {pattern}"
+                    );
+                    Segment::Wildcard(raw_segment[WILDCARD_PREFIX.len_utf8()..].to_owned())
+                }
+                _ => Segment::Literal((*raw_segment).to_owned()),
+            })
+            .collect();
+
+        Self(segments)
+    }
+
+    fn matches(&self, path_segments: &[String]) -> Option<HashMap<String, String>> {
+        let mut params = HashMap::new();
+
+        for (index, segment) in self.0.iter().enumerate() {
+            match segment {
+                Segment::Literal(literal) => {
+                    if path_segments.get(index) != Some(literal) {
+                        return None;
+                    }
+                }
+                Segment::Param(name) => {
+                    params.insert(name.clone(), path_segments.get(index)?.clone());
+                }
+                Segment::Wildcard(name) => {
+                    let rest = path_segments
+                        .get(index..)
+                        .map(|rest| rest.join("/"))
+                        .unwrap_or_default();
+                    params.insert(name.clone(), rest);
+
+                    return Some(params);
+                }
+            }
+        }
+
+        (path_segments.len() == self.0.len()).then_some(params)
+    }
+
+    /// Literal segments rank above parameters and parameters above wildcards, compared from the
+    /// first segment on
+    fn specificity(&self) -> Vec<u8> {
+        self.0
+            .iter()
+            .map(|segment| match segment {
+                Segment::Literal(_) => 2,
+                Segment::Param(_) => 1,
+                Segment::Wildcard(_) => 0,
+            })
+            .collect()
+    }
+}
+
+struct Route {
+    request_type: RequestType,
+    pattern: PathPattern,
+    handler: Box<dyn Handler>,
+}
+
+#[derive(Default)]
+pub struct Router {
+    routes: Vec<Route>,
+    virtual_hosts: Vec<(String, Self)>,
+    error_format: ErrorFormat,
+    continue_check: Option<BoxedContinueCheck>,
+}
+
+impl Router {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Format of error bodies, both the ones of this router and the ones sent by the connection
+    /// when a request can't be parsed
+    #[must_use]
+    pub const fn error_format(mut self, error_format: ErrorFormat) -> Self {
+        self.error_format = error_format;
+        self
+    }
+
+    pub const fn get_error_format(&self) -> ErrorFormat {
+        self.error_format
+    }
+
+    /// Registers a handler for requests with the given method and path pattern
+    ///
+    /// Patterns are split by `/`, segments starting with `:` capture a single path segment and a
+    /// final segment starting with `*` captures the rest of the path. Captured values are
+    /// available through [`RequestMessage::path_param`]. When several patterns match, the one
+    /// with a literal segment where the others capture wins, registration order breaks ties.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a parameter has an empty name or a wildcard is not the last segment
+    #[must_use]
+    pub fn route<H: Handler>(
+        mut self,
+        request_type: RequestType,
+        pattern: &str,
+        handler: H,
+    ) -> Self {
+        self.routes.push(Route {
+            request_type,
+            pattern: PathPattern::parse(pattern),
+            handler: Box::new(handler),
+        });
+
+        self
+    }
+
+    #[must_use]
+    pub fn get<H: Handler>(self, pattern: &str, handler: H) -> Self {
+        self.route(RequestType::Get, pattern, handler)
+    }
+
+    #[must_use]
+    pub fn post<H: Handler>(self, pattern: &str, handler: H) -> Self {
+        self.route(RequestType::Post, pattern, handler)
+    }
+
+    #[must_use]
+    pub fn put<H: Handler>(self, pattern: &str, handler: H) -> Self {
+        self.route(RequestType::Put, pattern, handler)
+    }
+
+    #[must_use]
+    pub fn delete<H: Handler>(self, pattern: &str, handler: H) -> Self {
+        self.route(RequestType::Delete, pattern, handler)
+    }
+
+    #[must_use]
+    pub fn patch<H: Handler>(self, pattern: &str, handler: H) -> Self {
+        self.route(RequestType::Patch, pattern, handler)
+    }
+
+    /// Dispatches requests whose `Host` matches the pattern to a dedicated router
+    ///
+    /// The pattern is compared case-insensitively without the port and may start with `*.` to
+    /// match any subdomain. Requests for other hosts fall through to the routes of this router.
+    #[must_use]
+    pub fn virtual_host(mut self, pattern: &str, router: Self) -> Self {
+        self.virtual_hosts.push((pattern.to_owned(), router));
+        self
+    }
+
+    /// Decides whether a request sent with `Expect: 100-continue` may go on to send its body
+    ///
+    /// The check sees the request line and header fields before any of the body was read. An
+    /// error status such as 417 or 413 is sent as the final response instead of `100 Continue`.
+    /// Without a check every expectation is met.
+    #[must_use]
+    pub fn expect_continue<C>(mut self, check: C) -> Self
+    where
+        C: Fn(&RequestLine, &Header) -> Result<(), Status> + Send + Sync + 'static,
+    {
+        self.continue_check = Some(Box::new(check));
+        self
+    }
+
+    /// Runs the check registered with [`Router::expect_continue`] for the router serving the host
+    ///
+    /// # Errors
+    ///
+    /// Returns the status to refuse the request with
+    pub fn check_continue(
+        &self,
+        request_line: &RequestLine,
+        header: &Header,
+    ) -> Result<(), Status> {
+        if let Some(router) = self.virtual_host_router(header) {
+            return router.check_continue(request_line, header);
+        }
+
+        self.continue_check
+            .as_ref()
+            .map_or(Ok(()), |check| check(request_line, header))
+    }
+
+    fn virtual_host_router(&self, header: &Header) -> Option<&Self> {
+        let host = header.host.as_ref()?;
+
+        self.virtual_hosts
+            .iter()
+            .find(|(pattern, _)| host.matches(pattern))
+            .map(|(_, router)| router)
+    }
+
+    pub async fn handle(&self, mut request_message: RequestMessage) -> ResponseMessage {
+        if let Some(router) = self.virtual_host_router(&request_message.header) {
+            return Box::pin(router.handle(request_message)).await;
+        }
+
+        let request_type = request_message.request_line.request_type.clone();
+
+        // `OPTIONS *` asks about the server as a whole rather than a particular resource
+        let path_segments = match request_message.request_line.uri.form() {
+            TargetForm::Asterisk => None,
+            _ => Some(request_message.request_line.uri.segments().to_vec()),
+        };
+
+        if let Some(path_segments) = &path_segments {
+            // HEAD is answered by the GET handler unless it has its own, see RFC 9110 section 9.3.2
+            let route = self.find_route(&request_type, path_segments).or_else(|| {
+                (request_type == RequestType::Head)
+                    .then(|| self.find_route(&RequestType::Get, path_segments))
+                    .flatten()
+            });
+
+            if let Some((route, path_params)) = route {
+                request_message.path_params = path_params;
+                return route.handler.call(request_message).await;
+            }
+        }
+
+        let allowed_request_types = self.allowed_request_types(path_segments.as_deref());
+
+        if allowed_request_types.is_empty() {
+            return error::error_response(Status::NOT_FOUND, None, self.error_format);
+        }
+
+        let allow = allowed_request_types
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut response = if request_type == RequestType::Options {
+            ResponseMessage::builder().empty()
+        } else {
+            error::error_response(Status::METHOD_NOT_ALLOWED, None, self.error_format)
+        };
+
+        response
+            .header
+            .other_headers
+            .insert(ALLOW_HEADER_NAME, allow);
+
+        response
+    }
+
+    fn find_route(
+        &self,
+        request_type: &RequestType,
+        path_segments: &[String],
+    ) -> Option<(&Route, HashMap<String, String>)> {
+        self.routes
+            .iter()
+            .filter(|route| &route.request_type == request_type)
+            .filter_map(|route| {
+                route
+                    .pattern
+                    .matches(path_segments)
+                    .map(|params| (route, params))
+            })
+            // The first of the most specific routes
+            .min_by_key(|(route, _)| Reverse(route.pattern.specificity()))
+    }
+
+    /// Methods allowed for the path, or for any path at all if there is none
+    fn allowed_request_types(&self, path_segments: Option<&[String]>) -> Vec<RequestType> {
+        let mut allowed_request_types = Vec::new();
+
+        let mut allow = |request_type: RequestType| {
+            if !allowed_request_types.contains(&request_type) {
+                allowed_request_types.push(request_type);
+            }
+        };
+
+        let mut path_matched = false;
+
+        for route in &self.routes {
+            if path_segments
+                .is_some_and(|path_segments| route.pattern.matches(path_segments).is_none())
+            {
+                continue;
+            }
+
+            path_matched = true;
+
+            allow(route.request_type.clone());
+            if route.request_type == RequestType::Get {
+                allow(RequestType::Head);
+            }
+        }
+
+        if path_matched {
+            allow(RequestType::Options);
+        }
+
+        allowed_request_types
+    }
+}
+
+impl Handler for Router {
+    fn call(&self, request_message: RequestMessage) -> HandlerFuture<'_> {
+        Box::pin(self.handle(request_message))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{body::Body, header::ContentLength, header_map::HeaderMap};
+
+    fn request(request_line: &str) -> RequestMessage {
+        let mut header_map = HeaderMap::new();
+        header_map.insert("host", "localhost".to_owned());
+
+        RequestMessage::new(
+            request_line.parse().unwrap(),
+            Header::try_from(&mut header_map).unwrap(),
+            Body::default(),
+        )
+    }
+
+    async fn handle(router: &Router, request_line: &str) -> (Status, String) {
+        let response = router.handle(request(request_line)).await;
+        (
+            response.response_line.status,
+            String::from_utf8_lossy(&response.body.bytes()).into_owned(),
+        )
+    }
+
+    fn echo_params(names: &'static [&'static str]) -> impl Handler {
+        move |request_message: RequestMessage| async move {
+            names
+                .iter()
+                .map(|name| format!("{name}={}", request_message.path_param(name).unwrap_or("-")))
+                .collect::<Vec<_>>()
+                .join(" ")
+        }
+    }
+
+    #[tokio::test]
+    async fn captures_params_and_wildcards() {
+        let router = Router::new()
+            .get("/users/:id/posts/:post", echo_params(&["id", "post"]))
+            .get("/files/*path", echo_params(&["path"]));
+
+        assert_eq!(
+            handle(&router, "GET /users/7/posts/hello HTTP/1.1").await,
+            (Status::OK, "id=7 post=hello".to_owned())
+        );
+        assert_eq!(
+            handle(&router, "GET /files/a/b/c.txt HTTP/1.1").await,
+            (Status::OK, "path=a/b/c.txt".to_owned())
+        );
+        assert_eq!(
+            handle(&router, "GET /files HTTP/1.1").await,
+            (Status::OK, "path=".to_owned())
+        );
+
+        // A parameter captures exactly one segment
+        for request_line in [
+            "GET /users/7/posts HTTP/1.1",
+            "GET /users/7/posts/a/b HTTP/1.1",
+        ] {
+            assert_eq!(handle(&router, request_line).await.0, Status::NOT_FOUND);
+        }
+    }
+
+    #[tokio::test]
+    async fn prefers_literal_segments_over_captures() {
+        let router = Router::new()
+            .get("/*rest", |_| async { "wildcard" })
+            .get("/users/:id", |_| async { "param" })
+            .get("/users/me", |_| async { "literal" })
+            .get("/users/:id", |_| async { "shadowed" });
+
+        for (request_line, expected) in [
+            ("GET /users/me HTTP/1.1", "literal"),
+            ("GET /users/7 HTTP/1.1", "param"),
+            ("GET /about HTTP/1.1", "wildcard"),
+        ] {
+            assert_eq!(
+                handle(&router, request_line).await,
+                (Status::OK, expected.to_owned())
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn tells_unknown_paths_from_unsupported_methods() {
+        let router = Router::new()
+            .get("/items", |_| async { "list" })
+            .post("/items", |_| async { "create" });
+
+        assert_eq!(
+            handle(&router, "GET /missing HTTP/1.1").await.0,
+            Status::NOT_FOUND
+        );
+
+        let response = router.handle(request("DELETE /items HTTP/1.1")).await;
+        assert_eq!(response.response_line.status, Status::METHOD_NOT_ALLOWED);
+        assert_eq!(
+            response.header.other_headers.get(ALLOW_HEADER_NAME),
+            Some("GET, HEAD, POST, OPTIONS")
+        );
+    }
+
+    #[tokio::test]
+    async fn answers_options_with_the_allowed_methods_and_no_content() {
+        let router = Router::new()
+            .get("/items", |_| async { "list" })
+            .delete("/items/:id", |_| async { "deleted" });
+
+        for (request_line, allow) in [
+            ("OPTIONS /items HTTP/1.1", "GET, HEAD, OPTIONS"),
+            ("OPTIONS * HTTP/1.1", "GET, HEAD, DELETE, OPTIONS"),
+        ] {
+            let response = router.handle(request(request_line)).await;
+
+            assert_eq!(response.response_line.status, Status::OK);
+            assert_eq!(
+                response.header.other_headers.get(ALLOW_HEADER_NAME),
+                Some(allow)
+            );
+            assert!(response.header.content_type.is_none());
+            assert_eq!(
+                response.header.content_length.map(ContentLength::get),
+                Some(0)
+            );
+            assert!(response.body.bytes().is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn answers_head_with_the_get_handler_unless_it_has_its_own() {
+        let router = Router::new().get("/page", |_| async { "page" });
+        assert_eq!(
+            handle(&router, "HEAD /page HTTP/1.1").await,
+            (Status::OK, "page".to_owned())
+        );
+
+        let router = router.route(RequestType::Head, "/page", |_| async { "head" });
+        assert_eq!(
+            handle(&router, "HEAD /page HTTP/1.1").await,
+            (Status::OK, "head".to_owned())
+        );
+
+        // Only GET routes stand in for HEAD
+        let router = Router::new().post("/form", |_| async { "form" });
+        assert_eq!(
+            handle(&router, "HEAD /form HTTP/1.1").await.0,
+            Status::METHOD_NOT_ALLOWED
+        );
+    }
+}
@@ -0,0 +1,281 @@
+use std::{future::Future, io, net::SocketAddr, sync::Arc};
+
+use thiserror::Error;
+use tokio::{
+    net::{TcpListener, TcpStream},
+    task::JoinSet,
+};
+use tokio_rustls::TlsAcceptor;
+
+use crate::{config::ConnectionConfig, response, router::Router, shutdown::Shutdown};
+
+const DEFAULT_ADDRESS: &str = "127.0.0.1:8080";
+
+#[derive(Error, Debug)]
+pub enum ServerError {
+    #[error("Failed to bind {0}: {1:?}")]
+    BindError(String, #[source] io::Error),
+
+    #[error("Failed to drain connections: {0:?}")]
+    DrainError(#[from] tokio::task::JoinError),
+}
+
+/// Listener addresses, routes and connection settings of a [`Server`]
+pub struct ServerBuilder {
+    address: String,
+    tls: Option<(String, TlsAcceptor)>,
+    router: Router,
+    config: ConnectionConfig,
+}
+
+impl ServerBuilder {
+    /// Address of the plain listener, `127.0.0.1:8080` unless set
+    #[must_use]
+    pub fn bind(mut self, address: impl Into<String>) -> Self {
+        self.address = address.into();
+        self
+    }
+
+    /// Adds a listener that terminates TLS with the acceptor before serving HTTP
+    #[must_use]
+    pub fn tls(mut self, address: impl Into<String>, acceptor: TlsAcceptor) -> Self {
+        self.tls = Some((address.into(), acceptor));
+        self
+    }
+
+    #[must_use]
+    pub fn router(mut self, router: Router) -> Self {
+        self.router = router;
+        self
+    }
+
+    #[must_use]
+    pub const fn config(mut self, config: ConnectionConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Binds the listeners, connections are only accepted once the server runs
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if one of the addresses can't be bound
+    pub async fn build(self) -> Result<Server, ServerError> {
+        let mut listeners = vec![(bind(&self.address).await?, None)];
+
+        if let Some((address, acceptor)) = self.tls {
+            listeners.push((bind(&address).await?, Some(acceptor)));
+        }
+
+        Ok(Server {
+            listeners,
+            router: Arc::new(self.router),
+            config: Arc::new(self.config),
+        })
+    }
+}
+
+async fn bind(address: &str) -> Result<TcpListener, ServerError> {
+    let listener = TcpListener::bind(address)
+        .await
+        .map_err(|err| ServerError::BindError(address.to_owned(), err))?;
+
+    tracing::info!("Listening on {address}");
+    Ok(listener)
+}
+
+/// Serves HTTP/1.1 and HTTP/2 on its listeners until it's shut down
+pub struct Server {
+    listeners: Vec<(TcpListener, Option<TlsAcceptor>)>,
+    router: Arc<Router>,
+    config: Arc<ConnectionConfig>,
+}
+
+impl Server {
+    pub fn builder() -> ServerBuilder {
+        ServerBuilder {
+            address: DEFAULT_ADDRESS.to_owned(),
+            tls: None,
+            router: Router::new(),
+            config: ConnectionConfig::default(),
+        }
+    }
+
+    /// Addresses the listeners were bound to, which tells the ports picked for port 0
+    pub fn local_addrs(&self) -> Vec<SocketAddr> {
+        self.listeners
+            .iter()
+            .filter_map(|(listener, _)| listener.local_addr().ok())
+            .collect()
+    }
+
+    /// Serves until SIGTERM or SIGINT is received, then drains the open connections
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the connections of a listener can't be drained
+    pub async fn run(self) -> Result<DrainSummary, ServerError> {
+        self.run_until(crate::shutdown::signal_received()).await
+    }
+
+    /// Serves until `signal` completes, then stops accepting connections and gives the open
+    /// ones up to the shutdown timeout to finish their requests
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the connections of a listener can't be drained
+    pub async fn run_until(
+        self,
+        signal: impl Future<Output = ()>,
+    ) -> Result<DrainSummary, ServerError> {
+        let (shutdown_trigger, shutdown) = Shutdown::channel();
+
+        let listeners = self
+            .listeners
+            .into_iter()
+            .map(|(listener, acceptor)| {
+                tokio::spawn(serve(
+                    listener,
+                    acceptor,
+                    Arc::clone(&self.config),
+                    Arc::clone(&self.router),
+                    shutdown.clone(),
+                ))
+            })
+            .collect::<Vec<_>>();
+
+        signal.await;
+        tracing::info!(
+            "Shutting down, waiting up to {}s for open connections",
+            self.config.shutdown_timeout.as_secs()
+        );
+        shutdown_trigger.send_replace(true);
+
+        let mut summary = DrainSummary::default();
+        for listener in listeners {
+            let drained = listener.await?;
+            summary.drained += drained.drained;
+            summary.aborted += drained.aborted;
+        }
+
+        tracing::info!(
+            "Shutdown complete, {} connections drained and {} aborted",
+            summary.drained,
+            summary.aborted
+        );
+
+        Ok(summary)
+    }
+}
+
+/// Connections that were still open when shutdown began
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DrainSummary {
+    /// Connections that finished their requests in time
+    pub drained: usize,
+    /// Connections cut off at the shutdown timeout
+    pub aborted: usize,
+}
+
+/// Accepts connections on the listener until shutdown, terminating TLS first when given an
+/// acceptor, then waits for the open connections up to the shutdown timeout
+async fn serve(
+    listener: TcpListener,
+    acceptor: Option<TlsAcceptor>,
+    config: Arc<ConnectionConfig>,
+    router: Arc<Router>,
+    mut shutdown: Shutdown,
+) -> DrainSummary {
+    let mut connections = JoinSet::new();
+
+    loop {
+        let (stream, _) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(conn) => conn,
+                Err(err) => {
+                    tracing::error!("Failed to accept connection: {err:?}");
+                    continue;
+                }
+            },
+            // Finished connections are reaped so the set only holds open ones
+            Some(joined) = connections.join_next() => {
+                if let Err(err) = joined {
+                    tracing::error!("Connection task failed: {err:?}");
+                }
+                continue;
+            }
+            () = shutdown.triggered() => break,
+        };
+
+        // Responses and HTTP/2 frames are written whole, so waiting to coalesce them only adds
+        // latency
+        if let Err(err) = stream.set_nodelay(true) {
+            tracing::debug!("Failed to disable Nagle's algorithm: {err:?}");
+        }
+
+        connections.spawn(connection(
+            stream,
+            acceptor.clone(),
+            Arc::clone(&config),
+            Arc::clone(&router),
+            shutdown.clone(),
+        ));
+    }
+
+    // New connections are refused from here on
+    drop(listener);
+
+    let open = connections.len();
+    let drain = async { while connections.join_next().await.is_some() {} };
+
+    let aborted = if tokio::time::timeout(config.shutdown_timeout, drain)
+        .await
+        .is_ok()
+    {
+        0
+    } else {
+        let aborted = connections.len();
+        connections.shutdown().await;
+        aborted
+    };
+
+    DrainSummary {
+        drained: open - aborted,
+        aborted,
+    }
+}
+
+async fn connection(
+    stream: TcpStream,
+    acceptor: Option<TlsAcceptor>,
+    config: Arc<ConnectionConfig>,
+    router: Arc<Router>,
+    shutdown: Shutdown,
+) {
+    let result = match acceptor {
+        Some(acceptor) => {
+            let handshake =
+                tokio::time::timeout(config.header_read_timeout, acceptor.accept(stream)).await;
+
+            match handshake {
+                Ok(Ok(stream)) => response::handle(stream, true, config, router, shutdown).await,
+                Ok(Err(err)) => {
+                    tracing::debug!("TLS handshake failed: {err:?}");
+                    return;
+                }
+                Err(_) => {
+                    tracing::debug!("TLS handshake timed out");
+                    return;
+                }
+            }
+        }
+        None => response::handle(stream, false, config, router, shutdown).await,
+    };
+
+    match result {
+        Ok(served_requests) => {
+            tracing::info!("Connection closed after {served_requests} requests");
+        }
+        Err(err) => tracing::error!("Error while handling incoming stream: {err:?}"),
+    }
+}
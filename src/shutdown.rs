@@ -0,0 +1,60 @@
+use tokio::sync::watch;
+
+/// Tells connections that the server is shutting down, so they finish the request in flight and
+/// close instead of waiting for the next one
+#[derive(Debug, Clone)]
+pub struct Shutdown(watch::Receiver<bool>);
+
+impl Shutdown {
+    pub fn channel() -> (watch::Sender<bool>, Self) {
+        let (sender, receiver) = watch::channel(false);
+        (sender, Self(receiver))
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    /// Completes once shutdown is triggered, never if the sender is gone without triggering it
+    pub async fn triggered(&mut self) {
+        if self.0.wait_for(|triggered| *triggered).await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+/// Completes on SIGTERM, as sent by process managers on deploys
+#[cfg(unix)]
+async fn terminate() {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    match signal(SignalKind::terminate()) {
+        Ok(mut terminate) => {
+            terminate.recv().await;
+        }
+        Err(err) => {
+            tracing::error!("Failed to listen for SIGTERM: {err:?}");
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+// Other platforms have no SIGTERM, only Ctrl-C is listened for
+#[cfg(not(unix))]
+async fn terminate() {
+    std::future::pending::<()>().await;
+}
+
+/// Completes on SIGTERM where there is one, or on SIGINT
+pub async fn signal_received() {
+    tokio::select! {
+        result = tokio::signal::ctrl_c() => {
+            if let Err(err) = result {
+                tracing::error!("Failed to listen for SIGINT: {err:?}");
+                std::future::pending::<()>().await;
+            }
+            tracing::info!("Received SIGINT");
+        }
+        () = terminate() => tracing::info!("Received SIGTERM"),
+    }
+}
@@ -0,0 +1,139 @@
+use std::{fs::File, io::BufReader, sync::Arc};
+
+use rustls::{
+    crypto::ring::sign::any_supported_type,
+    server::{ClientHello, ResolvesServerCert},
+    sign::CertifiedKey,
+    ServerConfig,
+};
+use thiserror::Error;
+use tokio_rustls::TlsAcceptor;
+
+use crate::types::header::Host;
+
+const CERT_ENV: &str = "TLS_CERT";
+const KEY_ENV: &str = "TLS_KEY";
+const SNI_ENV: &str = "TLS_SNI";
+
+// Protocol identifier of HTTP/1.1 in ALPN, see RFC 7301 section 6
+const ALPN_HTTP_1_1: &[u8] = b"http/1.1";
+
+#[derive(Error, Debug)]
+pub enum TlsError {
+    #[error("Failed to read {0}: {1:?}")]
+    ReadError(String, std::io::Error),
+
+    #[error("No certificate found in {0}")]
+    MissingCertificate(String),
+
+    #[error("No private key found in {0}")]
+    MissingPrivateKey(String),
+
+    #[error("{0} is set without {1}")]
+    IncompleteCertificate(&'static str, &'static str),
+
+    #[error("Invalid SNI entry, expected name=cert,key: {0}")]
+    InvalidSniEntry(String),
+
+    #[error("Rustls error: {0:?}")]
+    RustlsError(#[from] rustls::Error),
+}
+
+/// Picks the certificate matching the server name the client asked for, see RFC 6066 section 3
+#[derive(Debug)]
+struct CertificateResolver {
+    // Patterns follow virtual hosts, so `*.example.com` matches any subdomain
+    by_name: Vec<(String, Arc<CertifiedKey>)>,
+    // Used for clients without SNI and names without a certificate of their own
+    default: Option<Arc<CertifiedKey>>,
+}
+
+impl ResolvesServerCert for CertificateResolver {
+    fn resolve(&self, client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        let host = client_hello
+            .server_name()
+            .and_then(|name| name.parse::<Host>().ok());
+
+        host.and_then(|host| {
+            self.by_name
+                .iter()
+                .find(|(pattern, _)| host.matches(pattern))
+        })
+        .map(|(_, certified_key)| certified_key)
+        .or(self.default.as_ref())
+        .cloned()
+    }
+}
+
+/// Builds the acceptor of the HTTPS listener, if any certificate is configured
+///
+/// `TLS_CERT` and `TLS_KEY` hold the paths of the default certificate chain and private key in
+/// PEM. `TLS_SNI` adds certificates for specific names as `;` separated `name=cert,key` entries.
+///
+/// # Errors
+///
+/// Returns an error if only one of `TLS_CERT` and `TLS_KEY` is set, or if a certificate or key
+/// can't be read or they don't belong together
+pub fn acceptor_from_env() -> Result<Option<TlsAcceptor>, TlsError> {
+    let default = match (std::env::var(CERT_ENV), std::env::var(KEY_ENV)) {
+        (Ok(cert_path), Ok(key_path)) => Some(load_certified_key(&cert_path, &key_path)?),
+        (Ok(_), Err(_)) => return Err(TlsError::IncompleteCertificate(CERT_ENV, KEY_ENV)),
+        (Err(_), Ok(_)) => return Err(TlsError::IncompleteCertificate(KEY_ENV, CERT_ENV)),
+        (Err(_), Err(_)) => None,
+    };
+
+    let by_name = std::env::var(SNI_ENV)
+        .unwrap_or_default()
+        .split(';')
+        .filter(|entry| !entry.trim().is_empty())
+        .map(|entry| {
+            let (name, paths) = entry
+                .split_once('=')
+                .ok_or_else(|| TlsError::InvalidSniEntry(entry.to_owned()))?;
+            let (cert_path, key_path) = paths
+                .split_once(',')
+                .ok_or_else(|| TlsError::InvalidSniEntry(entry.to_owned()))?;
+
+            Ok((
+                name.trim().to_owned(),
+                load_certified_key(cert_path.trim(), key_path.trim())?,
+            ))
+        })
+        .collect::<Result<Vec<_>, TlsError>>()?;
+
+    if default.is_none() && by_name.is_empty() {
+        return Ok(None);
+    }
+
+    let mut config = ServerConfig::builder()
+        .with_no_client_auth()
+        .with_cert_resolver(Arc::new(CertificateResolver { by_name, default }));
+    config.alpn_protocols = vec![ALPN_HTTP_1_1.to_vec()];
+
+    Ok(Some(TlsAcceptor::from(Arc::new(config))))
+}
+
+fn load_certified_key(cert_path: &str, key_path: &str) -> Result<Arc<CertifiedKey>, TlsError> {
+    let open = |path: &str| {
+        File::open(path)
+            .map(BufReader::new)
+            .map_err(|err| TlsError::ReadError(path.to_owned(), err))
+    };
+
+    let certs = rustls_pemfile::certs(&mut open(cert_path)?)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| TlsError::ReadError(cert_path.to_owned(), err))?;
+    if certs.is_empty() {
+        return Err(TlsError::MissingCertificate(cert_path.to_owned()));
+    }
+
+    let key = rustls_pemfile::private_key(&mut open(key_path)?)
+        .map_err(|err| TlsError::ReadError(key_path.to_owned(), err))?
+        .ok_or_else(|| TlsError::MissingPrivateKey(key_path.to_owned()))?;
+
+    let certified_key = CertifiedKey::new(certs, any_supported_type(&key)?);
+    // Catches a key that belongs to another certificate at startup rather than at the handshake
+    certified_key.keys_match()?;
+
+    Ok(Arc::new(certified_key))
+}
@@ -1,6 +1,17 @@
+use std::{borrow::Cow, io::Cursor};
+
+use serde::de::DeserializeOwned;
 use thiserror::Error;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt},
+    sync::mpsc,
+};
 
 use super::header;
+use crate::multipart;
+
+const STREAM_READ_BUFFER_SIZE: usize = 8 * 1024;
+const UTF_8_CHARSET: &str = "utf-8";
 
 #[derive(Error, Debug)]
 pub enum ParseError {
@@ -9,6 +20,15 @@ pub enum ParseError {
 
     #[error("Invalid JSON: {0:?}")]
     InvalidJson(#[from] serde_json::Error),
+
+    #[error("Invalid form: {0:?}")]
+    InvalidForm(#[from] serde_urlencoded::de::Error),
+
+    #[error("Body is not valid {0}")]
+    InvalidCharsetData(String),
+
+    #[error("Unsupported charset: {0}")]
+    UnsupportedCharset(String),
 }
 
 #[derive(Debug, Default)]
@@ -22,13 +42,110 @@ impl Body {
     pub const fn get_type(&self) -> &BodyType {
         &self.0
     }
+
+    pub const fn get_type_mut(&mut self) -> &mut BodyType {
+        &mut self.0
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub enum BodyType {
     TextPlain(String),
     TextHtml(String),
     ApplicationJson(serde_json::Value),
+    // Pairs in the order they were sent, keys may repeat
+    ApplicationFormUrlencoded(Vec<(String, String)>),
+    Binary(Vec<u8>),
+    Stream(BodyStream),
+}
+
+impl BodyType {
+    /// Bytes of the body as sent on the wire, streams are written chunk by chunk instead
+    pub fn as_bytes(&self) -> Cow<'_, [u8]> {
+        match self {
+            Self::TextPlain(text) | Self::TextHtml(text) => Cow::Borrowed(text.as_bytes()),
+            Self::ApplicationJson(json) => Cow::Owned(json.to_string().into_bytes()),
+            Self::ApplicationFormUrlencoded(params) => Cow::Owned(encode_form(params).into_bytes()),
+            Self::Binary(data) => Cow::Borrowed(data),
+            Self::Stream(_) => Cow::Borrowed(&[]),
+        }
+    }
+}
+
+enum StreamSource {
+    Reader(Box<dyn AsyncRead + Send + Unpin>),
+    Channel(mpsc::Receiver<Vec<u8>>),
+}
+
+pub struct BodyStream {
+    source: StreamSource,
+    length: Option<u64>,
+}
+
+impl BodyStream {
+    pub fn from_reader(reader: impl AsyncRead + Send + Unpin + 'static) -> Self {
+        Self {
+            source: StreamSource::Reader(Box::new(reader)),
+            length: None,
+        }
+    }
+
+    pub fn channel(buffer: usize) -> (mpsc::Sender<Vec<u8>>, Self) {
+        let (sender, receiver) = mpsc::channel(buffer);
+
+        (
+            sender,
+            Self {
+                source: StreamSource::Channel(receiver),
+                length: None,
+            },
+        )
+    }
+
+    #[must_use]
+    pub const fn with_length(mut self, length: u64) -> Self {
+        self.length = Some(length);
+        self
+    }
+
+    pub const fn length(&self) -> Option<u64> {
+        self.length
+    }
+
+    /// Returns the next non-empty chunk of the body or `None` once the body is exhausted
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the source of the stream fails
+    pub async fn next_chunk(&mut self) -> std::io::Result<Option<Vec<u8>>> {
+        match &mut self.source {
+            StreamSource::Reader(reader) => {
+                let mut chunk = vec![0u8; STREAM_READ_BUFFER_SIZE];
+                let read = reader.read(&mut chunk).await?;
+
+                if read == 0 {
+                    return Ok(None);
+                }
+
+                chunk.truncate(read);
+                Ok(Some(chunk))
+            }
+            StreamSource::Channel(receiver) => loop {
+                match receiver.recv().await {
+                    Some(chunk) if chunk.is_empty() => {}
+                    chunk => return Ok(chunk),
+                }
+            },
+        }
+    }
+}
+
+impl std::fmt::Debug for BodyStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BodyStream")
+            .field("length", &self.length)
+            .finish_non_exhaustive()
+    }
 }
 
 impl std::fmt::Display for BodyType {
@@ -40,6 +157,10 @@ impl std::fmt::Display for BodyType {
                 Self::TextPlain(text) => text.clone(),
                 Self::TextHtml(html) => html.clone(),
                 Self::ApplicationJson(json) => json.to_string(),
+                Self::ApplicationFormUrlencoded(params) => encode_form(params),
+                Self::Binary(data) => String::from_utf8_lossy(data).into_owned(),
+                // Streams are written chunk by chunk and can't be rendered upfront
+                Self::Stream(_) => String::new(),
             }
         )
     }
@@ -51,22 +172,132 @@ impl Default for BodyType {
     }
 }
 
+// Request bodies are kept as received, handlers pick how to interpret them
 impl Body {
-    pub fn parse(
-        body_data: Vec<u8>,
+    pub fn bytes(&self) -> Cow<'_, [u8]> {
+        self.0.as_bytes()
+    }
+
+    /// Decodes the body as text in the charset of its content type, UTF-8 when none is given
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the charset is unsupported or the body is not valid in it
+    pub fn text(&self, content_type: &header::ContentType) -> Result<String, ParseError> {
+        decode_text(
+            self.bytes().into_owned(),
+            content_type.charset().unwrap_or(UTF_8_CHARSET),
+        )
+    }
+
+    /// JSON is always UTF-8 regardless of any charset parameter, see RFC 8259 section 8.1
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the body is not JSON of the expected shape
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, ParseError> {
+        Ok(serde_json::from_slice(&self.bytes())?)
+    }
+
+    /// Key/value pairs of an `application/x-www-form-urlencoded` body, which is always UTF-8
+    pub fn form_params(&self) -> Vec<(String, String)> {
+        url::form_urlencoded::parse(&self.bytes())
+            .into_owned()
+            .collect()
+    }
+
+    /// # Errors
+    ///
+    /// Returns an error if the body is not a form of the expected shape
+    pub fn form<T: DeserializeOwned>(&self) -> Result<T, ParseError> {
+        Ok(serde_urlencoded::from_bytes(&self.bytes())?)
+    }
+
+    /// Reads the parts of a multipart body, as they arrive when the body is streamed
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the content type is not multipart or lacks a valid boundary
+    pub fn into_multipart(
+        self,
         content_type: &header::ContentType,
-    ) -> Result<Self, ParseError> {
-        let body_str = String::from_utf8(body_data)?;
-
-        let body = match content_type {
-            header::ContentType::TextPlain => Self(BodyType::TextPlain(body_str)),
-            header::ContentType::TextHtml => Self(BodyType::TextHtml(body_str)),
-            header::ContentType::ApplicationJson => {
-                Self(BodyType::ApplicationJson(serde_json::from_str(&body_str)?))
-            }
+        limits: multipart::Limits,
+    ) -> Result<multipart::Multipart, multipart::ParseError> {
+        let source = match self.0 {
+            BodyType::Stream(stream) => stream,
+            body_type => BodyStream::from_reader(Cursor::new(body_type.as_bytes().into_owned())),
         };
 
-        Ok(body)
+        multipart::Multipart::new(source, content_type, limits)
+    }
+
+    /// Interprets the body according to its media type, types without a variant of their own are
+    /// kept as binary
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the body doesn't match its media type
+    pub fn decode(&self, content_type: &header::ContentType) -> Result<BodyType, ParseError> {
+        if content_type.is_json() {
+            return Ok(BodyType::ApplicationJson(self.json()?));
+        }
+
+        Ok(match content_type.mime_type() {
+            "application/x-www-form-urlencoded" => {
+                BodyType::ApplicationFormUrlencoded(self.form_params())
+            }
+            "text/html" => BodyType::TextHtml(self.text(content_type)?),
+            mime_type if mime_type.starts_with("text/") => {
+                BodyType::TextPlain(self.text(content_type)?)
+            }
+            _ => BodyType::Binary(self.bytes().into_owned()),
+        })
+    }
+}
+
+fn encode_form(params: &[(String, String)]) -> String {
+    url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(params)
+        .finish()
+}
+
+// Charsets are matched by their IANA names and common aliases, which are case-insensitive
+fn decode_text(data: Vec<u8>, charset: &str) -> Result<String, ParseError> {
+    let invalid = || ParseError::InvalidCharsetData(charset.to_owned());
+
+    match charset.to_ascii_lowercase().as_str() {
+        "utf-8" | "utf8" => Ok(String::from_utf8(data)?),
+        "us-ascii" | "ascii" if data.is_ascii() => Ok(String::from_utf8(data)?),
+        "us-ascii" | "ascii" => Err(invalid()),
+        // Every byte of Latin-1 maps to the code point of the same value
+        "iso-8859-1" | "latin1" | "l1" => Ok(data.into_iter().map(char::from).collect()),
+        encoding @ ("utf-16" | "utf-16be" | "utf-16le") => {
+            if !data.len().is_multiple_of(2) {
+                return Err(invalid());
+            }
+
+            // Without a BOM UTF-16 is big-endian, see RFC 2781 section 4.3
+            let (little_endian, data) = match (encoding, data.as_slice()) {
+                ("utf-16", [0xFF, 0xFE, rest @ ..]) => (true, rest),
+                ("utf-16", [0xFE, 0xFF, rest @ ..]) => (false, rest),
+                _ => (encoding == "utf-16le", data.as_slice()),
+            };
+
+            let units = data
+                .chunks_exact(2)
+                .map(|unit| {
+                    let unit = [unit[0], unit[1]];
+                    if little_endian {
+                        u16::from_le_bytes(unit)
+                    } else {
+                        u16::from_be_bytes(unit)
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            String::from_utf16(&units).map_err(|_| invalid())
+        }
+        _ => Err(ParseError::UnsupportedCharset(charset.to_owned())),
     }
 }
 
@@ -1,39 +1,51 @@
-use std::{collections::HashMap, str::FromStr};
+use std::{borrow::Cow, str::FromStr};
 
 use thiserror::Error;
-use url::Url;
 
-const HOST_HEADER_NAME: &str = "host";
+use super::{
+    header_map::{HeaderMap, TypedHeader},
+    request_line::is_token,
+};
+
+pub const HOST_HEADER_NAME: &str = "host";
 const CONTENT_TYPE_HEADER_NAME: &str = "content-type";
-const CONTENT_LENGTH_HEADER_NAME: &str = "content-length";
-
-macro_rules! parse_required_field {
-    ($map:expr, $key:expr, $type:path) => {{
-        let raw = $map
-            .remove($key)
-            .ok_or_else(|| ParseError::MissingHeader(format!("Missing header: {}", $key)))?;
-        raw.parse()?
-    }};
-}
+pub const CONTENT_LENGTH_HEADER_NAME: &str = "content-length";
+const CONTENT_DISPOSITION_HEADER_NAME: &str = "content-disposition";
+pub const TRANSFER_ENCODING_HEADER_NAME: &str = "transfer-encoding";
+const EXPECT_HEADER_NAME: &str = "expect";
+pub const CONNECTION_HEADER_NAME: &str = "connection";
+pub const KEEP_ALIVE_HEADER_NAME: &str = "keep-alive";
+pub const ALLOW_HEADER_NAME: &str = "allow";
+
+const CLOSE_CONNECTION_OPTION: &str = "close";
+const KEEP_ALIVE_CONNECTION_OPTION: &str = "keep-alive";
+const CHUNKED_TRANSFER_CODING: &str = "chunked";
+const CONTINUE_EXPECTATION: &str = "100-continue";
+const CHARSET_PARAMETER_NAME: &str = "charset";
+const BOUNDARY_PARAMETER_NAME: &str = "boundary";
+const NAME_PARAMETER_NAME: &str = "name";
+const FILENAME_PARAMETER_NAME: &str = "filename";
+// Whitespace allowed around parameter delimiters, see RFC 9110 section 5.6.3
+const OPTIONAL_WHITESPACE: [char; 2] = [' ', '\t'];
 
 macro_rules! parse_optional_field {
     ($map:expr, $key:expr, $type:path, $default:expr) => {{
-        $map.remove($key)
+        single_value($map.remove($key), $key)?
             .map(|v| v.parse())
             .unwrap_or(Ok($default))?
     }};
 }
 
-fn capitalize(word: &str) -> String {
-    word.split('-')
-        .map(|part| {
-            let mut chars = part.chars();
-            chars.next().map_or_else(String::new, |first| {
-                first.to_uppercase().collect::<String>() + &chars.as_str().to_ascii_lowercase()
-            })
-        })
-        .collect::<Vec<_>>()
-        .join("-")
+// Singleton fields may only be repeated with the very same value, see RFC 9110 section 5.3
+fn single_value(values: Vec<String>, key: &str) -> Result<Option<String>, ParseError> {
+    let mut values = values.into_iter();
+    let first = values.next();
+
+    if values.any(|value| Some(&value) != first.as_ref()) {
+        return Err(ParseError::DuplicateHeader(key.to_owned()));
+    }
+
+    Ok(first)
 }
 
 #[derive(Error, Debug)]
@@ -41,8 +53,20 @@ pub enum ParseError {
     #[error("Missing header: {0}")]
     MissingHeader(String),
 
-    #[error("Invalid url provided: {0:?}")]
-    InvalidUrl(#[from] url::ParseError),
+    #[error("Header is repeated with different values: {0}")]
+    DuplicateHeader(String),
+
+    #[error("Header must not be repeated: {0}")]
+    RepeatedHeader(String),
+
+    #[error("Invalid date: {0:?}")]
+    InvalidDate(#[from] httpdate::Error),
+
+    #[error("Invalid host provided: {0:?}")]
+    InvalidHost(#[from] url::ParseError),
+
+    #[error("Invalid port provided: {0}")]
+    InvalidPort(String),
 
     #[error("Failed to parse as number: {0:?}")]
     ParseIntError(#[from] std::num::ParseIntError),
@@ -50,39 +74,115 @@ pub enum ParseError {
     #[error("Failed to parse as number: {0:?}")]
     TryFromIntError(#[from] std::num::TryFromIntError),
 
-    #[error("Unsupported ContentType: {0}")]
-    UnsupportedContentType(String),
+    #[error("Invalid Content-Type: {0}")]
+    InvalidContentType(String),
+
+    #[error("Invalid Content-Disposition: {0}")]
+    InvalidContentDisposition(String),
+
+    #[error("Unsupported Connection option: {0}")]
+    UnsupportedConnection(String),
+
+    #[error("Unsupported Transfer-Encoding: {0}")]
+    UnsupportedTransferEncoding(String),
+
+    #[error("Unsupported expectation: {0}")]
+    UnsupportedExpectation(String),
+
+    #[error("Both Content-Length and Transfer-Encoding headers are present")]
+    ConflictingFraming,
+
+    #[error("Transfer-Encoding is present in an HTTP/1.0 message")]
+    TransferEncodingInHttp10,
+
+    #[error("Invalid content length: {0}")]
+    InvalidContentLength(String),
+
+    #[error("Header field line is folded onto the previous one")]
+    ObsoleteLineFolding,
+
+    #[error("Invalid header field line: {0}")]
+    InvalidFieldLine(String),
 }
 
 #[derive(Debug)]
 pub struct Header {
-    pub host: Host,
+    pub host: Option<Host>,
     pub content_type: ContentType,
     pub content_length: ContentLength,
-    pub other_headers: OtherHeaders,
+    pub transfer_encoding: Option<TransferEncoding>,
+    pub other_headers: HeaderMap,
+}
+
+impl From<&Header> for HeaderMap {
+    fn from(header: &Header) -> Self {
+        let mut header_map = Self::new();
+
+        if let Some(host) = &header.host {
+            header_map.append_typed(host);
+        }
+
+        header_map.append_typed(&header.content_type);
+
+        // Content-Length must not be sent together with Transfer-Encoding
+        match &header.transfer_encoding {
+            Some(transfer_encoding) => header_map.append_typed(transfer_encoding),
+            None => header_map.append_typed(&header.content_length),
+        }
+
+        for (name, value) in header.other_headers.iter() {
+            header_map.append(name, value);
+        }
+
+        header_map
+    }
 }
 
 impl std::fmt::Display for Header {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}\r\n{}\r\n{}\r\n{}",
-            self.host, self.content_type, self.content_length, self.other_headers
-        )
+        write!(f, "{}", HeaderMap::from(self))
     }
 }
 
-impl TryFrom<&mut HashMap<String, String>> for Header {
+impl Header {
+    pub fn connection(&self) -> Option<Connection> {
+        self.other_headers
+            .get_typed::<Connection>()
+            .and_then(Result::ok)
+    }
+
+    pub fn expect(&self) -> Option<Result<Expect, ParseError>> {
+        self.other_headers.get_typed::<Expect>()
+    }
+}
+
+impl TryFrom<&mut HeaderMap> for Header {
     type Error = ParseError;
 
-    fn try_from(value: &mut HashMap<String, String>) -> Result<Self, Self::Error> {
+    fn try_from(value: &mut HeaderMap) -> Result<Self, Self::Error> {
+        // A message with both framing headers is a potential request smuggling attempt, see RFC
+        // 9112 section 6.3
+        if value.contains(CONTENT_LENGTH_HEADER_NAME)
+            && value.contains(TRANSFER_ENCODING_HEADER_NAME)
+        {
+            return Err(ParseError::ConflictingFraming);
+        }
+
+        // Unlike other singleton fields, Host must not be repeated at all, see RFC 9112 section 3.2
+        if value.get_all(HOST_HEADER_NAME).count() > 1 {
+            return Err(ParseError::RepeatedHeader(HOST_HEADER_NAME.to_owned()));
+        }
+
         Ok(Self {
-            host: parse_required_field!(value, HOST_HEADER_NAME, Host),
+            host: single_value(value.remove(HOST_HEADER_NAME), HOST_HEADER_NAME)?
+                .map(|v| v.parse())
+                .transpose()?,
             content_type: parse_optional_field!(
                 value,
                 CONTENT_TYPE_HEADER_NAME,
                 ContentType,
-                ContentType::TextPlain
+                // Bodies of unknown type are to be treated as opaque data, see RFC 9110 section 8.3
+                ContentType::APPLICATION_OCTET_STREAM
             ),
             content_length: parse_optional_field!(
                 value,
@@ -90,67 +190,366 @@ impl TryFrom<&mut HashMap<String, String>> for Header {
                 ContentLength,
                 ContentLength::default()
             ),
-            other_headers: value.clone().into(),
+            transfer_encoding: single_value(
+                value.remove(TRANSFER_ENCODING_HEADER_NAME),
+                TRANSFER_ENCODING_HEADER_NAME,
+            )?
+            .map(|v| v.parse())
+            .transpose()?,
+            other_headers: std::mem::take(value),
         })
     }
 }
 
-#[derive(Debug)]
-pub struct Host(String);
+/// `host[:port]` as defined by RFC 3986 section 3.2.2, the host may be a registered name, an
+/// IPv4 address or a bracketed IPv6 literal
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Host {
+    host: String,
+    port: Option<u16>,
+}
+
+impl Host {
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    pub const fn port(&self) -> Option<u16> {
+        self.port
+    }
+
+    /// Matches the host name against a pattern, which may start with `*.` to match any subdomain
+    pub fn matches(&self, pattern: &str) -> bool {
+        let host = self.host.to_ascii_lowercase();
+        let pattern = pattern.to_ascii_lowercase();
+
+        pattern.strip_prefix("*.").map_or_else(
+            || host == pattern,
+            |suffix| {
+                host.strip_suffix(suffix)
+                    .is_some_and(|subdomain| subdomain.len() > 1 && subdomain.ends_with('.'))
+            },
+        )
+    }
+}
 
 impl FromStr for Host {
     type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Url::parse(s)?;
+        let s = s.trim();
+
+        let (host, port) = match s.find(']') {
+            Some(end) if s.starts_with('[') => {
+                let port = match &s[end + 1..] {
+                    "" => None,
+                    rest => Some(
+                        rest.strip_prefix(':')
+                            .ok_or_else(|| ParseError::InvalidPort(rest.to_owned()))?,
+                    ),
+                };
+
+                (&s[..=end], port)
+            }
+            _ => s
+                .rsplit_once(':')
+                .map_or((s, None), |(host, port)| (host, Some(port))),
+        };
+
+        // An empty Host is allowed when the target has no authority, see RFC 9112 section 3.2
+        if !host.is_empty() {
+            url::Host::parse(host)?;
+        }
+
+        let port = port
+            .filter(|port| !port.is_empty())
+            .map(|port| {
+                port.parse()
+                    .map_err(|_| ParseError::InvalidPort(port.to_owned()))
+            })
+            .transpose()?;
+
+        Ok(Self {
+            host: host.to_owned(),
+            port,
+        })
+    }
+}
 
-        Ok(Self(s.to_owned()))
+impl TypedHeader for Host {
+    const NAME: &'static str = HOST_HEADER_NAME;
+
+    fn encode(&self) -> String {
+        self.port
+            .map_or_else(|| self.host.clone(), |port| format!("{}:{port}", self.host))
     }
 }
 
 impl std::fmt::Display for Host {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}: {}", capitalize(HOST_HEADER_NAME), self.0)
+        write!(f, "{}", self.encode())
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
-pub enum ContentType {
-    TextPlain,
-    TextHtml,
-    ApplicationJson,
+/// Media type of a body along with its parameters, see RFC 9110 section 8.3.1
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentType {
+    // `type/subtype`, always lowercase
+    mime_type: Cow<'static, str>,
+    parameters: Vec<(String, String)>,
+}
+
+impl ContentType {
+    pub const TEXT_PLAIN: Self = Self::from_static("text/plain");
+    pub const TEXT_HTML: Self = Self::from_static("text/html");
+    pub const APPLICATION_JSON: Self = Self::from_static("application/json");
+    pub const APPLICATION_PROBLEM_JSON: Self = Self::from_static("application/problem+json");
+    pub const APPLICATION_OCTET_STREAM: Self = Self::from_static("application/octet-stream");
+    pub const APPLICATION_FORM_URLENCODED: Self =
+        Self::from_static("application/x-www-form-urlencoded");
+
+    const fn from_static(mime_type: &'static str) -> Self {
+        Self {
+            mime_type: Cow::Borrowed(mime_type),
+            parameters: Vec::new(),
+        }
+    }
+
+    pub fn mime_type(&self) -> &str {
+        &self.mime_type
+    }
+
+    /// Value of a parameter such as `charset` or `boundary`, names are case-insensitive
+    pub fn parameter(&self, name: &str) -> Option<&str> {
+        self.parameters
+            .iter()
+            .find(|(parameter, _)| parameter.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn charset(&self) -> Option<&str> {
+        self.parameter(CHARSET_PARAMETER_NAME)
+    }
+
+    /// Delimiter of the parts of a multipart body, see RFC 2046 section 5.1.1
+    pub fn boundary(&self) -> Option<&str> {
+        self.parameter(BOUNDARY_PARAMETER_NAME)
+    }
+
+    pub fn is_multipart(&self) -> bool {
+        self.mime_type.starts_with("multipart/")
+    }
+
+    /// Sets a parameter, replacing any previous value, the name must be a token
+    #[must_use]
+    pub fn with_parameter(mut self, name: &str, value: &str) -> Self {
+        let name = name.to_ascii_lowercase();
+        // Charset names are case-insensitive, see RFC 9110 section 8.3.2
+        let value = if name == CHARSET_PARAMETER_NAME {
+            value.to_ascii_lowercase()
+        } else {
+            value.to_owned()
+        };
+
+        self.parameters.retain(|(parameter, _)| *parameter != name);
+        self.parameters.push((name, value));
+        self
+    }
+
+    /// Whether both have the same media type, regardless of their parameters
+    pub fn has_mime_type(&self, other: &Self) -> bool {
+        self.mime_type == other.mime_type
+    }
+
+    /// JSON itself or any media type using the `+json` structured syntax suffix, see RFC 6839
+    pub fn is_json(&self) -> bool {
+        self.mime_type == Self::APPLICATION_JSON.mime_type || self.mime_type.ends_with("+json")
+    }
 }
 
+/// Parses `type/subtype` followed by parameters, see RFC 9110 section 8.3.1
+///
+/// Type, subtype and parameter names are case-insensitive and kept in lowercase, so that displaying
+/// the result and parsing it again always gives back an equal value.
 impl FromStr for ContentType {
     type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let sanitized_s = s.split_once(';').map_or(s, |(before, _)| before);
+        let invalid = || Self::Err::InvalidContentType(s.to_owned());
+
+        // Neither the type nor the subtype may contain a quote, so the first `;` ends them
+        let (mime_type, parameters) = s.split_once(';').unwrap_or((s, ""));
 
-        match sanitized_s {
-            "text/plain" => Ok(Self::TextPlain),
-            "application/json" => Ok(Self::ApplicationJson),
-            unknown => Err(Self::Err::UnsupportedContentType(format!(
-                "Unsupported content type: {unknown}"
-            ))),
+        let mime_type = mime_type.trim_matches(OPTIONAL_WHITESPACE);
+        match mime_type.split_once('/') {
+            Some((type_, subtype)) if is_token(type_) && is_token(subtype) => {}
+            _ => return Err(invalid()),
         }
+
+        let content_type = Self {
+            mime_type: Cow::Owned(mime_type.to_ascii_lowercase()),
+            parameters: Vec::new(),
+        };
+
+        Ok(parse_parameters(parameters)
+            .ok_or_else(invalid)?
+            .iter()
+            .fold(content_type, |content_type, (name, value)| {
+                content_type.with_parameter(name, value)
+            }))
+    }
+}
+
+/// Parses `;` separated parameters whose values are tokens or quoted strings, as they follow media
+/// types and dispositions, returns None if they are malformed
+fn parse_parameters(mut s: &str) -> Option<Vec<(String, String)>> {
+    let mut parameters = Vec::new();
+
+    loop {
+        s = s.trim_start_matches(OPTIONAL_WHITESPACE);
+
+        // Empty parameters are allowed, e.g. a trailing `;`
+        if let Some(after_delimiter) = s.strip_prefix(';') {
+            s = after_delimiter;
+            continue;
+        }
+
+        if s.is_empty() {
+            return Some(parameters);
+        }
+
+        let (name, after_name) = s.split_once('=')?;
+        if !is_token(name) {
+            return None;
+        }
+
+        let (value, after_value) = if let Some(quoted) = after_name.strip_prefix('"') {
+            parse_quoted_string(quoted)?
+        } else {
+            let end = after_name.find(';').unwrap_or(after_name.len());
+            let value = after_name[..end].trim_end_matches(OPTIONAL_WHITESPACE);
+            if !is_token(value) {
+                return None;
+            }
+
+            (value.to_owned(), &after_name[end..])
+        };
+
+        s = after_value.trim_start_matches(OPTIONAL_WHITESPACE);
+        if !s.is_empty() && !s.starts_with(';') {
+            return None;
+        }
+
+        parameters.push((name.to_ascii_lowercase(), value));
+    }
+}
+
+/// Reads a quoted string up to its closing quote, returning the unescaped value and the rest of
+/// the input, see RFC 9110 section 5.6.4
+fn parse_quoted_string(s: &str) -> Option<(String, &str)> {
+    let mut value = String::new();
+    let mut chars = s.char_indices();
+
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '"' => return Some((value, &s[index + 1..])),
+            '\\' => value.push(chars.next()?.1),
+            c => value.push(c),
+        }
+    }
+
+    None
+}
+
+// Values that aren't tokens have to be quoted to survive parsing
+fn encode_parameters(value: String, parameters: &[(String, String)]) -> String {
+    parameters.iter().fold(value, |encoded, (name, value)| {
+        if is_token(value) {
+            format!("{encoded}; {name}={value}")
+        } else {
+            let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+            format!("{encoded}; {name}=\"{escaped}\"")
+        }
+    })
+}
+
+impl TypedHeader for ContentType {
+    const NAME: &'static str = CONTENT_TYPE_HEADER_NAME;
+
+    fn encode(&self) -> String {
+        encode_parameters(self.mime_type.to_string(), &self.parameters)
     }
 }
 
 impl std::fmt::Display for ContentType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let content_type_str = match self {
-            Self::TextPlain => "text/plain",
-            Self::TextHtml => "text/html",
-            Self::ApplicationJson => "application/json",
-        };
+        write!(f, "{}", self.encode())
+    }
+}
 
-        write!(
-            f,
-            "{}: {}",
-            capitalize(CONTENT_TYPE_HEADER_NAME),
-            content_type_str
-        )
+/// Disposition of a body or of a part of a multipart body, see RFC 6266 section 4 and RFC 7578
+/// section 4.2
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentDisposition {
+    // Always lowercase, e.g. `form-data` or `attachment`
+    disposition_type: String,
+    parameters: Vec<(String, String)>,
+}
+
+impl ContentDisposition {
+    pub fn disposition_type(&self) -> &str {
+        &self.disposition_type
+    }
+
+    pub fn parameter(&self, name: &str) -> Option<&str> {
+        self.parameters
+            .iter()
+            .find(|(parameter, _)| parameter.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Name of the form field a part belongs to
+    pub fn name(&self) -> Option<&str> {
+        self.parameter(NAME_PARAMETER_NAME)
+    }
+
+    /// File name as sent by the client, which must not be trusted as a path
+    pub fn filename(&self) -> Option<&str> {
+        self.parameter(FILENAME_PARAMETER_NAME)
+    }
+}
+
+impl FromStr for ContentDisposition {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Self::Err::InvalidContentDisposition(s.to_owned());
+
+        let (disposition_type, parameters) = s.split_once(';').unwrap_or((s, ""));
+
+        let disposition_type = disposition_type.trim_matches(OPTIONAL_WHITESPACE);
+        if !is_token(disposition_type) {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            disposition_type: disposition_type.to_ascii_lowercase(),
+            parameters: parse_parameters(parameters).ok_or_else(invalid)?,
+        })
+    }
+}
+
+impl TypedHeader for ContentDisposition {
+    const NAME: &'static str = CONTENT_DISPOSITION_HEADER_NAME;
+
+    fn encode(&self) -> String {
+        encode_parameters(self.disposition_type.clone(), &self.parameters)
+    }
+}
+
+impl std::fmt::Display for ContentDisposition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.encode())
     }
 }
 
@@ -161,19 +560,36 @@ impl ContentLength {
     pub const fn new(content_length: u64) -> Self {
         Self(content_length)
     }
+
+    pub const fn get(self) -> u64 {
+        self.0
+    }
 }
 
 impl FromStr for ContentLength {
     type Err = ParseError;
 
+    // Only digits are allowed, unlike the signs `u64::from_str` accepts, see RFC 9110 section 8.6
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() || !s.bytes().all(|byte| byte.is_ascii_digit()) {
+            return Err(ParseError::InvalidContentLength(s.to_owned()));
+        }
+
         Ok(Self(s.parse()?))
     }
 }
 
+impl TypedHeader for ContentLength {
+    const NAME: &'static str = CONTENT_LENGTH_HEADER_NAME;
+
+    fn encode(&self) -> String {
+        self.0.to_string()
+    }
+}
+
 impl std::fmt::Display for ContentLength {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}: {}", capitalize(CONTENT_LENGTH_HEADER_NAME), self.0)
+        write!(f, "{}", self.encode())
     }
 }
 
@@ -184,25 +600,371 @@ impl TryInto<usize> for ContentLength {
     }
 }
 
-#[derive(Debug)]
-pub struct OtherHeaders(HashMap<String, String>);
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferEncoding {
+    Chunked,
+}
 
-impl From<HashMap<String, String>> for OtherHeaders {
-    fn from(value: HashMap<String, String>) -> Self {
-        Self(value)
+impl FromStr for TransferEncoding {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Chunked is the only coding we can decode, and it must be the final one anyway
+        if s.trim().eq_ignore_ascii_case(CHUNKED_TRANSFER_CODING) {
+            Ok(Self::Chunked)
+        } else {
+            Err(Self::Err::UnsupportedTransferEncoding(s.to_owned()))
+        }
     }
 }
 
-impl std::fmt::Display for OtherHeaders {
+impl TypedHeader for TransferEncoding {
+    const NAME: &'static str = TRANSFER_ENCODING_HEADER_NAME;
+
+    fn encode(&self) -> String {
+        match self {
+            Self::Chunked => CHUNKED_TRANSFER_CODING,
+        }
+        .to_owned()
+    }
+}
+
+impl std::fmt::Display for TransferEncoding {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            self.0
-                .iter()
-                .map(|(key, value)| format!("{}: {}", capitalize(key), value))
-                .collect::<Vec<_>>()
-                .join("\r\n")
-        )
+        write!(f, "{}", self.encode())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connection {
+    KeepAlive,
+    Close,
+}
+
+impl FromStr for Connection {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut options = s.split(',').map(str::trim);
+
+        if options
+            .clone()
+            .any(|option| option.eq_ignore_ascii_case(CLOSE_CONNECTION_OPTION))
+        {
+            return Ok(Self::Close);
+        }
+
+        if options.any(|option| option.eq_ignore_ascii_case(KEEP_ALIVE_CONNECTION_OPTION)) {
+            return Ok(Self::KeepAlive);
+        }
+
+        Err(Self::Err::UnsupportedConnection(s.to_owned()))
+    }
+}
+
+impl TypedHeader for Connection {
+    const NAME: &'static str = CONNECTION_HEADER_NAME;
+
+    fn encode(&self) -> String {
+        match self {
+            Self::KeepAlive => KEEP_ALIVE_CONNECTION_OPTION,
+            Self::Close => CLOSE_CONNECTION_OPTION,
+        }
+        .to_owned()
+    }
+}
+
+impl std::fmt::Display for Connection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.encode())
+    }
+}
+
+/// The only expectation defined by RFC 9110 section 10.1.1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expect {
+    Continue,
+}
+
+impl FromStr for Expect {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim().eq_ignore_ascii_case(CONTINUE_EXPECTATION) {
+            return Ok(Self::Continue);
+        }
+
+        Err(Self::Err::UnsupportedExpectation(s.to_owned()))
+    }
+}
+
+impl TypedHeader for Expect {
+    const NAME: &'static str = EXPECT_HEADER_NAME;
+
+    fn encode(&self) -> String {
+        match self {
+            Self::Continue => CONTINUE_EXPECTATION,
+        }
+        .to_owned()
+    }
+}
+
+impl std::fmt::Display for Expect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.encode())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CREDENTIALS: &str = "Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==";
+
+    fn header_map(fields: &[(&str, &str)]) -> HeaderMap {
+        let mut header_map = HeaderMap::new();
+        for (name, value) in fields {
+            header_map.append(name, *value);
+        }
+        header_map
+    }
+
+    #[test]
+    fn names_match_case_insensitively() {
+        let mut fields = header_map(&[
+            ("HOST", "Example.COM:8080"),
+            ("Content-TYPE", "Application/JSON"),
+            ("content-length", "2"),
+            ("AuThOrIzAtIoN", CREDENTIALS),
+        ]);
+
+        let header = Header::try_from(&mut fields).unwrap();
+        let host = header.host.unwrap();
+
+        assert_eq!(host.port(), Some(8080));
+        assert!(header
+            .content_type
+            .has_mime_type(&ContentType::APPLICATION_JSON));
+        assert_eq!(header.content_length.get(), 2);
+        for name in ["authorization", "AUTHORIZATION", "Authorization"] {
+            assert_eq!(header.other_headers.get(name), Some(CREDENTIALS));
+        }
+    }
+
+    #[test]
+    fn values_are_preserved_byte_for_byte() {
+        let values = [
+            ("authorization", CREDENTIALS),
+            ("x-token", "AbC-123_xYz=="),
+            ("cookie", "Session=MiXeD; Theme=Dark"),
+        ];
+        let mut fields = header_map(&values);
+
+        let header = Header::try_from(&mut fields).unwrap();
+
+        for (name, value) in values {
+            assert_eq!(
+                header
+                    .other_headers
+                    .get(&name.to_ascii_uppercase())
+                    .map(str::as_bytes),
+                Some(value.as_bytes())
+            );
+        }
+    }
+
+    #[test]
+    fn host_keeps_its_case() {
+        let host = "Example.COM".parse::<Host>().unwrap();
+
+        assert_eq!(host.host(), "Example.COM");
+        assert!(host.matches("example.com"));
+    }
+
+    #[test]
+    fn parses_hosts_with_ports_and_ip_literals() {
+        for (value, host, port) in [
+            ("example.com", "example.com", None),
+            ("example.com:8080", "example.com", Some(8080)),
+            // An empty port is the same as none, see RFC 3986 section 3.2.3
+            ("example.com:", "example.com", None),
+            ("127.0.0.1:80", "127.0.0.1", Some(80)),
+            ("[::1]", "[::1]", None),
+            ("[2001:db8::1]:443", "[2001:db8::1]", Some(443)),
+            ("", "", None),
+        ] {
+            let parsed = value.parse::<Host>().unwrap();
+            assert_eq!((parsed.host(), parsed.port()), (host, port), "{value}");
+            assert_eq!(parsed.encode(), value.trim_end_matches(':'));
+        }
+
+        for value in [
+            "example.com:http",
+            "example.com:65536",
+            "[::1]8080",
+            "[::1]:port",
+            "[::g]",
+            "::1",
+            "exa mple.com",
+        ] {
+            assert!(value.parse::<Host>().is_err(), "{value}");
+        }
+    }
+
+    #[test]
+    fn host_patterns_match_names_and_subdomains() {
+        let host = "API.Example.com:8080".parse::<Host>().unwrap();
+
+        assert!(host.matches("api.example.com"));
+        assert!(host.matches("*.EXAMPLE.com"));
+        assert!(!host.matches("example.com"));
+        assert!(!host.matches("*.api.example.com"));
+
+        // The wildcard stands for whole labels only
+        let host = "badexample.com".parse::<Host>().unwrap();
+        assert!(!host.matches("*.example.com"));
+        assert!(!"example.com"
+            .parse::<Host>()
+            .unwrap()
+            .matches("*.example.com"));
+        assert!(!".example.com"
+            .parse::<Host>()
+            .is_ok_and(|host| host.matches("*.example.com")));
+    }
+
+    #[test]
+    fn host_must_not_be_repeated() {
+        for values in [["a.com", "a.com"], ["a.com", "b.com"]] {
+            let mut fields = header_map(&[("Host", values[0]), ("host", values[1])]);
+
+            assert!(matches!(
+                Header::try_from(&mut fields),
+                Err(ParseError::RepeatedHeader(name)) if name == HOST_HEADER_NAME
+            ));
+        }
+    }
+
+    #[test]
+    fn repeated_fields_with_different_case_are_one_field() {
+        let mut fields = header_map(&[("Content-Length", "2"), ("CONTENT-LENGTH", "3")]);
+
+        assert!(matches!(
+            Header::try_from(&mut fields),
+            Err(ParseError::DuplicateHeader(_))
+        ));
+    }
+
+    fn round_trip(content_type: &ContentType) -> ContentType {
+        content_type.to_string().parse().unwrap()
+    }
+
+    #[test]
+    fn content_type_display_is_only_the_value() {
+        let content_type = "text/html; charset=utf-8".parse::<ContentType>().unwrap();
+
+        assert_eq!(content_type.to_string(), "text/html; charset=utf-8");
+        assert_eq!(ContentType::TEXT_HTML.to_string(), "text/html");
+    }
+
+    #[test]
+    fn typed_headers_display_only_their_value() {
+        use crate::types::response_header::{Date, Server};
+
+        for (displayed, value) in [
+            (
+                "example.com:8080".parse::<Host>().unwrap().to_string(),
+                "example.com:8080",
+            ),
+            (ContentLength::new(5).to_string(), "5"),
+            (
+                "chunked".parse::<TransferEncoding>().unwrap().to_string(),
+                "chunked",
+            ),
+            ("close".parse::<Connection>().unwrap().to_string(), "close"),
+            (
+                "100-continue".parse::<Expect>().unwrap().to_string(),
+                "100-continue",
+            ),
+            (
+                "form-data; name=a"
+                    .parse::<ContentDisposition>()
+                    .unwrap()
+                    .to_string(),
+                "form-data; name=a",
+            ),
+            (Server::new("test").to_string(), "test"),
+            (
+                Date::new(std::time::UNIX_EPOCH).to_string(),
+                "Thu, 01 Jan 1970 00:00:00 GMT",
+            ),
+        ] {
+            assert_eq!(displayed, value);
+        }
+    }
+
+    #[test]
+    fn header_section_names_every_field() {
+        let mut fields = header_map(&[
+            ("host", "example.com"),
+            ("content-type", "text/plain"),
+            ("content-length", "2"),
+            ("x-custom", "a"),
+        ]);
+        let header = Header::try_from(&mut fields).unwrap();
+
+        assert_eq!(
+            header.to_string(),
+            "Host: example.com\r\nContent-Type: text/plain\r\nContent-Length: 2\r\nX-Custom: a"
+        );
+    }
+
+    #[test]
+    fn content_type_round_trips_text_html() {
+        let content_type = "text/html".parse::<ContentType>().unwrap();
+
+        assert_eq!(content_type, ContentType::TEXT_HTML);
+        assert_eq!(round_trip(&content_type), content_type);
+        assert_eq!(round_trip(&ContentType::TEXT_HTML), ContentType::TEXT_HTML);
+    }
+
+    #[test]
+    fn content_type_round_trips_mixed_case() {
+        let content_type = "Text/HTML; Charset=UTF-8; Level=One"
+            .parse::<ContentType>()
+            .unwrap();
+
+        assert_eq!(content_type.mime_type(), "text/html");
+        assert_eq!(content_type.charset(), Some("utf-8"));
+        // Only charset values are case-insensitive, other values keep their case
+        assert_eq!(content_type.parameter("LEVEL"), Some("One"));
+        assert_eq!(round_trip(&content_type), content_type);
+    }
+
+    #[test]
+    fn content_type_round_trips_quoted_parameters() {
+        let content_type = r#"multipart/form-data; boundary="a b;c\"d\\e"; name=plain"#
+            .parse::<ContentType>()
+            .unwrap();
+
+        assert_eq!(content_type.boundary(), Some(r#"a b;c"d\e"#));
+        assert_eq!(content_type.parameter("name"), Some("plain"));
+        assert_eq!(round_trip(&content_type), content_type);
+    }
+
+    #[test]
+    fn content_type_rejects_malformed_values() {
+        for value in [
+            "",
+            "text",
+            "text/",
+            "/html",
+            "text/html; charset",
+            r#"a/b; c="d"#,
+        ] {
+            assert!(
+                value.parse::<ContentType>().is_err(),
+                "{value:?} should not parse"
+            );
+        }
     }
 }
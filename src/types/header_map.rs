@@ -0,0 +1,239 @@
+use std::str::FromStr;
+
+pub fn capitalize(word: &str) -> String {
+    word.split('-')
+        .map(|part| {
+            let mut chars = part.chars();
+            chars.next().map_or_else(String::new, |first| {
+                first.to_uppercase().collect::<String>() + &chars.as_str().to_ascii_lowercase()
+            })
+        })
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Header with a well-known name whose value can be parsed and rendered on its own
+///
+/// Like [`std::fmt::Display`] of the implementing types, `encode` yields only the value, the name
+/// is added by the [`HeaderMap`] the header is rendered through.
+pub trait TypedHeader: FromStr {
+    const NAME: &'static str;
+
+    fn encode(&self) -> String;
+}
+
+/// Header fields in the order they were added
+///
+/// Names are compared case-insensitively and stored lowercased, a name can have multiple values
+/// (e.g. `Set-Cookie`), each of them is rendered as its own field line.
+#[derive(Debug, Default, Clone)]
+pub struct HeaderMap(Vec<(String, String)>);
+
+impl HeaderMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub const fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// First value of the field
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> {
+        self.0
+            .iter()
+            .filter(move |(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn get_typed<H: TypedHeader>(&self) -> Option<Result<H, H::Err>> {
+        self.get(H::NAME).map(str::parse)
+    }
+
+    /// Replaces all values of the field, keeping the position of the first one
+    pub fn insert(&mut self, name: &str, value: impl Into<String>) {
+        let name = name.to_ascii_lowercase();
+        let value = value.into();
+
+        match self.0.iter().position(|(key, _)| *key == name) {
+            Some(position) => {
+                self.0[position].1 = value;
+
+                let mut first = true;
+                self.0.retain(|(key, _)| {
+                    if *key != name {
+                        return true;
+                    }
+
+                    std::mem::replace(&mut first, false)
+                });
+            }
+            None => self.0.push((name, value)),
+        }
+    }
+
+    pub fn insert_typed<H: TypedHeader>(&mut self, header: &H) {
+        self.insert(H::NAME, header.encode());
+    }
+
+    pub fn append(&mut self, name: &str, value: impl Into<String>) {
+        self.0.push((name.to_ascii_lowercase(), value.into()));
+    }
+
+    pub fn append_typed<H: TypedHeader>(&mut self, header: &H) {
+        self.append(H::NAME, header.encode());
+    }
+
+    /// Removes the field and returns all of its values
+    pub fn remove(&mut self, name: &str) -> Vec<String> {
+        let mut removed = Vec::new();
+
+        self.0.retain(|(key, value)| {
+            if key.eq_ignore_ascii_case(name) {
+                removed.push(value.clone());
+                false
+            } else {
+                true
+            }
+        });
+
+        removed
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+}
+
+impl std::fmt::Display for HeaderMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            self.0
+                .iter()
+                .map(|(key, value)| format!("{}: {}", capitalize(key), value))
+                .collect::<Vec<_>>()
+                .join("\r\n")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::header::ContentLength;
+
+    fn header_map(fields: &[(&str, &str)]) -> HeaderMap {
+        let mut header_map = HeaderMap::new();
+        for (name, value) in fields {
+            header_map.append(name, *value);
+        }
+        header_map
+    }
+
+    #[test]
+    fn looks_up_names_case_insensitively() {
+        let mut header_map = header_map(&[("X-Request-ID", "abc")]);
+
+        for name in ["x-request-id", "X-REQUEST-ID", "x-Request-Id"] {
+            assert_eq!(header_map.get(name), Some("abc"));
+            assert!(header_map.contains(name));
+        }
+
+        // Names are stored lowercased, values as they are
+        assert_eq!(
+            header_map.iter().collect::<Vec<_>>(),
+            [("x-request-id", "abc")]
+        );
+        assert_eq!(header_map.remove("X-Request-Id"), ["abc"]);
+        assert!(header_map.is_empty());
+    }
+
+    #[test]
+    fn keeps_insertion_order() {
+        let header_map = header_map(&[("b", "1"), ("a", "2"), ("c", "3"), ("a", "4")]);
+
+        assert_eq!(
+            header_map.iter().collect::<Vec<_>>(),
+            [("b", "1"), ("a", "2"), ("c", "3"), ("a", "4")]
+        );
+        assert_eq!(header_map.to_string(), "B: 1\r\nA: 2\r\nC: 3\r\nA: 4");
+    }
+
+    #[test]
+    fn append_adds_values_and_insert_replaces_them() {
+        let mut header_map = header_map(&[
+            ("Set-Cookie", "a=1"),
+            ("Vary", "Accept"),
+            ("set-cookie", "b=2"),
+        ]);
+
+        assert_eq!(header_map.get("set-cookie"), Some("a=1"));
+        assert_eq!(
+            header_map.get_all("SET-COOKIE").collect::<Vec<_>>(),
+            ["a=1", "b=2"]
+        );
+        assert_eq!(header_map.get_all("missing").count(), 0);
+
+        // The first value's position is kept
+        header_map.insert("Set-Cookie", "c=3");
+        assert_eq!(
+            header_map.iter().collect::<Vec<_>>(),
+            [("set-cookie", "c=3"), ("vary", "Accept")]
+        );
+
+        header_map.insert("Cache-Control", "no-store");
+        assert_eq!(header_map.len(), 3);
+        assert_eq!(header_map.remove("vary"), ["Accept"]);
+        assert_eq!(header_map.remove("vary"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn typed_headers_use_their_name() {
+        let mut header_map = HeaderMap::new();
+
+        header_map.insert_typed(&ContentLength::new(5));
+        header_map.insert_typed(&ContentLength::new(7));
+        assert_eq!(header_map.get("Content-Length"), Some("7"));
+        assert_eq!(
+            header_map
+                .get_typed::<ContentLength>()
+                .map(|result| result.unwrap().get()),
+            Some(7)
+        );
+
+        header_map.append_typed(&ContentLength::new(9));
+        assert_eq!(
+            header_map.get_all("content-length").collect::<Vec<_>>(),
+            ["7", "9"]
+        );
+
+        header_map.insert("content-length", "nope");
+        assert!(header_map.get_typed::<ContentLength>().unwrap().is_err());
+    }
+
+    #[test]
+    fn capitalizes_each_word_of_a_name() {
+        assert_eq!(capitalize("content-type"), "Content-Type");
+        assert_eq!(capitalize("X-REQUEST-ID"), "X-Request-Id");
+        assert_eq!(capitalize("etag"), "Etag");
+    }
+}
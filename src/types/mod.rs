@@ -1,7 +1,9 @@
 pub mod body;
 pub mod header;
+pub mod header_map;
 pub mod request;
 pub mod request_line;
 pub mod response;
+pub mod response_header;
 pub mod response_line;
 pub mod status;
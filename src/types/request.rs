@@ -1,14 +1,21 @@
-use super::{body, header, request_line};
+use std::collections::HashMap;
+
+use super::{body, header, header_map, request_line, response};
 
 #[derive(Debug)]
 pub struct RequestMessage {
     pub request_line: request_line::RequestLine,
     pub header: header::Header,
     pub body: body::Body,
+    pub trailers: header_map::HeaderMap,
+    pub path_params: HashMap<String, String>,
+    /// Whether the request arrived over TLS
+    pub secure: bool,
+    pub interim: response::InterimSender,
 }
 
 impl RequestMessage {
-    pub const fn new(
+    pub fn new(
         request_line: request_line::RequestLine,
         header: header::Header,
         body: body::Body,
@@ -17,6 +24,29 @@ impl RequestMessage {
             request_line,
             header,
             body,
+            trailers: header_map::HeaderMap::default(),
+            path_params: HashMap::new(),
+            secure: false,
+            interim: response::InterimSender::default(),
+        }
+    }
+
+    /// Scheme the request was made with, for building absolute URLs such as redirect targets
+    pub const fn scheme(&self) -> &'static str {
+        if self.secure {
+            "https"
+        } else {
+            "http"
         }
     }
+
+    /// Sends a 1xx response such as 103 Early Hints before the handler returns the final one,
+    /// returns false if the client can't receive it
+    pub fn send_interim(&self, response: response::ResponseMessage) -> bool {
+        self.interim.send(response)
+    }
+
+    pub fn path_param(&self, name: &str) -> Option<&str> {
+        self.path_params.get(name).map(String::as_str)
+    }
 }
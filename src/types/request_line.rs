@@ -1,13 +1,32 @@
 use std::str::FromStr;
 
+use percent_encoding::percent_decode_str;
+use serde::de::DeserializeOwned;
 use thiserror::Error;
 
-const HTTP_PREFIX: &str = "http";
+const HTTP_PREFIX: &str = "HTTP";
 const FRONT_SLASH_PREFIX: &str = "/";
-const GET_METHOD_NAME: &str = "get";
-const POST_METHOD_NAME: &str = "post";
-const PUT_METHOD_NAME: &str = "put";
-const DELETE_METHOD_NAME: &str = "delete";
+const ASTERISK_TARGET: &str = "*";
+const SCHEME_SEPARATOR: &str = "://";
+const ENCODED_FRONT_SLASH: &str = "%2F";
+const GET_METHOD_NAME: &str = "GET";
+const POST_METHOD_NAME: &str = "POST";
+const PUT_METHOD_NAME: &str = "PUT";
+const DELETE_METHOD_NAME: &str = "DELETE";
+const HEAD_METHOD_NAME: &str = "HEAD";
+const OPTIONS_METHOD_NAME: &str = "OPTIONS";
+const PATCH_METHOD_NAME: &str = "PATCH";
+const TRACE_METHOD_NAME: &str = "TRACE";
+const CONNECT_METHOD_NAME: &str = "CONNECT";
+
+// Token characters from RFC 9110 section 5.6.2, methods must consist of them
+const TOKEN_SPECIAL_CHARS: &[u8] = b"!#$%&'*+-.^_`|~";
+
+pub fn is_token(s: &str) -> bool {
+    !s.is_empty()
+        && s.bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || TOKEN_SPECIAL_CHARS.contains(&byte))
+}
 
 #[derive(Error, Debug)]
 pub enum ParseError {
@@ -20,11 +39,16 @@ pub enum ParseError {
     #[error("Empty path provided")]
     EmtpyPath,
 
+    #[error("Invalid request target: {0}")]
+    InvalidRequestTarget(String),
+
     #[error("Invalid HTTP Version: {0}")]
     InvalidHttpVersion(String),
+
+    #[error("Unsupported HTTP Version: {0}")]
+    UnsupportedHttpVersion(String),
 }
 
-#[allow(dead_code)]
 #[derive(Debug)]
 pub struct RequestLine {
     pub request_type: RequestType,
@@ -45,20 +69,70 @@ impl FromStr for RequestLine {
             return Err(Self::Err::InvalidRequestLineLength(sanitized_s.len()));
         }
 
+        Self::new(
+            sanitized_s[0].parse()?,
+            sanitized_s[1].parse()?,
+            sanitized_s[2].parse()?,
+        )
+    }
+}
+
+impl RequestLine {
+    /// # Errors
+    ///
+    /// Returns an error if the target is not allowed with the method
+    pub fn new(
+        request_type: RequestType,
+        uri: Path,
+        http_version: HttpVersion,
+    ) -> Result<Self, ParseError> {
+        // Asterisk-form is only meant for OPTIONS and authority-form only for CONNECT, see RFC
+        // 9112 section 3.2
+        let form_allowed = match uri.form() {
+            TargetForm::Asterisk => request_type == RequestType::Options,
+            TargetForm::Authority => request_type == RequestType::Connect,
+            TargetForm::Origin | TargetForm::Absolute => request_type != RequestType::Connect,
+        };
+
+        if !form_allowed {
+            return Err(ParseError::InvalidRequestTarget(format!(
+                "{} is not allowed for {request_type}",
+                uri.get_path()
+            )));
+        }
+
         Ok(Self {
-            request_type: sanitized_s[0].parse()?,
-            uri: sanitized_s[1].parse()?,
-            http_version: sanitized_s[2].parse()?,
+            request_type,
+            uri,
+            http_version,
         })
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum RequestType {
     Get,
+    Head,
     Post,
     Put,
     Delete,
+    Connect,
+    Options,
+    Trace,
+    Patch,
+    Extension(String),
+}
+
+impl RequestType {
+    /// Safe methods are read-only, see RFC 9110 section 9.2.1
+    pub const fn is_safe(&self) -> bool {
+        matches!(self, Self::Get | Self::Head | Self::Options | Self::Trace)
+    }
+
+    /// Idempotent methods can be retried without changing the outcome, see RFC 9110 section 9.2.2
+    pub const fn is_idempotent(&self) -> bool {
+        self.is_safe() || matches!(self, Self::Put | Self::Delete)
+    }
 }
 
 impl FromStr for RequestType {
@@ -67,9 +141,15 @@ impl FromStr for RequestType {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         Ok(match s {
             GET_METHOD_NAME => Self::Get,
+            HEAD_METHOD_NAME => Self::Head,
             POST_METHOD_NAME => Self::Post,
             PUT_METHOD_NAME => Self::Put,
             DELETE_METHOD_NAME => Self::Delete,
+            CONNECT_METHOD_NAME => Self::Connect,
+            OPTIONS_METHOD_NAME => Self::Options,
+            TRACE_METHOD_NAME => Self::Trace,
+            PATCH_METHOD_NAME => Self::Patch,
+            extension if is_token(extension) => Self::Extension(extension.to_owned()),
             unknown => {
                 return Err(Self::Err::InvalidRequestType(unknown.to_owned()));
             }
@@ -77,15 +157,142 @@ impl FromStr for RequestType {
     }
 }
 
+impl std::fmt::Display for RequestType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let method_name = match self {
+            Self::Get => GET_METHOD_NAME,
+            Self::Head => HEAD_METHOD_NAME,
+            Self::Post => POST_METHOD_NAME,
+            Self::Put => PUT_METHOD_NAME,
+            Self::Delete => DELETE_METHOD_NAME,
+            Self::Connect => CONNECT_METHOD_NAME,
+            Self::Options => OPTIONS_METHOD_NAME,
+            Self::Trace => TRACE_METHOD_NAME,
+            Self::Patch => PATCH_METHOD_NAME,
+            Self::Extension(extension) => return write!(f, "{extension}"),
+        };
+
+        write!(f, "{method_name}")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetForm {
+    Origin,
+    Absolute,
+    Authority,
+    Asterisk,
+}
+
+/// Request target split into percent-decoded path segments and query parameters
+///
+/// Dot segments and empty segments are removed from the path, so `/a//b/../c` and `/a/c` are the
+/// same target. An encoded `%2F` stays part of its segment, the decoded path keeps it encoded so
+/// it can't be mistaken for a separator.
 #[derive(Debug)]
-pub struct Path(String);
+pub struct Path {
+    form: TargetForm,
+    authority: Option<String>,
+    normalized: String,
+    segments: Vec<String>,
+    query: Option<String>,
+    query_params: Vec<(String, String)>,
+}
 
 impl Path {
     pub fn get_path(&self) -> &str {
-        &self.0
+        &self.normalized
+    }
+
+    pub fn segments(&self) -> &[String] {
+        &self.segments
+    }
+
+    pub const fn form(&self) -> TargetForm {
+        self.form
+    }
+
+    /// Authority of absolute-form and authority-form targets
+    pub fn authority(&self) -> Option<&str> {
+        self.authority.as_deref()
+    }
+
+    pub fn query(&self) -> Option<&str> {
+        self.query.as_deref()
+    }
+
+    pub fn query_params(&self) -> &[(String, String)] {
+        &self.query_params
+    }
+
+    pub fn query_param(&self, name: &str) -> Option<&str> {
+        self.query_params
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// # Errors
+    ///
+    /// Returns an error if the query is not of the expected shape
+    pub fn query_as<T: DeserializeOwned>(&self) -> Result<T, serde_urlencoded::de::Error> {
+        serde_urlencoded::from_str(self.query.as_deref().unwrap_or_default())
+    }
+
+    fn new(
+        form: TargetForm,
+        authority: Option<String>,
+        raw_path: &str,
+        query: Option<&str>,
+    ) -> Self {
+        let segments = normalize_segments(raw_path);
+        let normalized = format!(
+            "{FRONT_SLASH_PREFIX}{}",
+            segments
+                .iter()
+                .map(|segment| segment.replace(FRONT_SLASH_PREFIX, ENCODED_FRONT_SLASH))
+                .collect::<Vec<_>>()
+                .join(FRONT_SLASH_PREFIX)
+        );
+
+        let query_params = query
+            .map(|query| {
+                url::form_urlencoded::parse(query.as_bytes())
+                    .into_owned()
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            form,
+            authority,
+            normalized,
+            segments,
+            query: query.map(ToOwned::to_owned),
+            query_params,
+        }
     }
 }
 
+// Segments are decoded before looking for dot segments, so `%2e%2e` can't escape the root either
+fn normalize_segments(raw_path: &str) -> Vec<String> {
+    let mut segments = Vec::new();
+
+    for raw_segment in raw_path.split(FRONT_SLASH_PREFIX) {
+        let segment = percent_decode_str(raw_segment).decode_utf8_lossy();
+
+        match segment.as_ref() {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            _ => segments.push(segment.into_owned()),
+        }
+    }
+
+    segments
+}
+
 impl FromStr for Path {
     type Err = ParseError;
 
@@ -94,13 +301,58 @@ impl FromStr for Path {
             return Err(Self::Err::EmtpyPath);
         }
 
-        Ok(Self(s.to_owned()))
+        if s == ASTERISK_TARGET {
+            return Ok(Self {
+                form: TargetForm::Asterisk,
+                authority: None,
+                normalized: ASTERISK_TARGET.to_owned(),
+                segments: Vec::new(),
+                query: None,
+                query_params: Vec::new(),
+            });
+        }
+
+        if s.starts_with(FRONT_SLASH_PREFIX) {
+            let (raw_path, query) = s
+                .split_once('?')
+                .map_or((s, None), |(raw_path, query)| (raw_path, Some(query)));
+
+            return Ok(Self::new(TargetForm::Origin, None, raw_path, query));
+        }
+
+        if s.contains(SCHEME_SEPARATOR) {
+            let url = url::Url::parse(s)
+                .map_err(|err| Self::Err::InvalidRequestTarget(format!("{s}: {err}")))?;
+
+            let authority = url.host_str().map(|host| {
+                url.port()
+                    .map_or_else(|| host.to_owned(), |port| format!("{host}:{port}"))
+            });
+
+            return Ok(Self::new(
+                TargetForm::Absolute,
+                authority,
+                url.path(),
+                url.query(),
+            ));
+        }
+
+        Ok(Self {
+            form: TargetForm::Authority,
+            authority: Some(s.to_owned()),
+            normalized: String::new(),
+            segments: Vec::new(),
+            query: None,
+            query_params: Vec::new(),
+        })
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum HttpVersionEnum {
+    V1_0,
     V1_1,
+    V2_0,
 }
 
 impl std::fmt::Display for HttpVersionEnum {
@@ -109,24 +361,45 @@ impl std::fmt::Display for HttpVersionEnum {
             f,
             "{}",
             match self {
+                Self::V1_0 => "1.0",
                 Self::V1_1 => "1.1",
+                Self::V2_0 => "2.0",
             }
         )
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct HttpVersion(HttpVersionEnum);
 
 impl HttpVersion {
     pub const fn new(http_version: HttpVersionEnum) -> Self {
         Self(http_version)
     }
+
+    /// Connections are persistent by default only since HTTP/1.1, see RFC 9112 section 9.3
+    pub const fn keeps_alive_by_default(self) -> bool {
+        !matches!(self.0, HttpVersionEnum::V1_0)
+    }
+
+    /// Chunked transfer coding doesn't exist in HTTP/1.0 and HTTP/2 has its own framing
+    pub const fn supports_chunked(self) -> bool {
+        matches!(self.0, HttpVersionEnum::V1_1)
+    }
+
+    pub const fn requires_host(self) -> bool {
+        matches!(self.0, HttpVersionEnum::V1_1)
+    }
+
+    /// HTTP/1.0 clients don't expect 1xx responses, see RFC 9110 section 15.2
+    pub const fn supports_interim_responses(self) -> bool {
+        !matches!(self.0, HttpVersionEnum::V1_0)
+    }
 }
 
 impl std::fmt::Display for HttpVersion {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}/{}", HTTP_PREFIX.to_ascii_uppercase(), self.0)
+        write!(f, "{}/{}", HTTP_PREFIX, self.0)
     }
 }
 
@@ -145,11 +418,157 @@ impl FromStr for HttpVersion {
                 Self::Err::InvalidHttpVersion(format!("Failed to strip front slash: {s}"))
             })?;
 
-        match sanitized_s {
-            "1.1" => Ok(Self(HttpVersionEnum::V1_1)),
-            unknown => Err(Self::Err::InvalidHttpVersion(format!(
-                "HTTP version {unknown} is not supported"
+        let (major, minor) = sanitized_s
+            .split_once('.')
+            .filter(|(major, minor)| {
+                [major, minor].iter().all(|digit| {
+                    digit.len() == 1 && digit.bytes().all(|byte| byte.is_ascii_digit())
+                })
+            })
+            .ok_or_else(|| Self::Err::InvalidHttpVersion(format!("Malformed HTTP version: {s}")))?;
+
+        // Higher minor versions of HTTP/1 are compatible with 1.1, see RFC 9110 section 2.5
+        match (major, minor) {
+            ("1", "0") => Ok(Self(HttpVersionEnum::V1_0)),
+            ("1", _) => Ok(Self(HttpVersionEnum::V1_1)),
+            _ => Err(Self::Err::UnsupportedHttpVersion(format!(
+                "HTTP version {sanitized_s} is not supported"
             ))),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn target_keeps_case_of_path_and_query() {
+        let request_line = "GET /Users/ABC?Q=X HTTP/1.1"
+            .parse::<RequestLine>()
+            .unwrap();
+
+        assert_eq!(request_line.request_type, RequestType::Get);
+        assert_eq!(request_line.uri.get_path(), "/Users/ABC");
+        assert_eq!(request_line.uri.segments(), ["Users", "ABC"]);
+        assert_eq!(request_line.uri.query(), Some("Q=X"));
+        assert_eq!(request_line.uri.query_param("Q"), Some("X"));
+        assert_eq!(request_line.uri.query_param("q"), None);
+    }
+
+    #[test]
+    fn absolute_target_keeps_case_of_path_and_query() {
+        let request_line = "GET http://example.com/Users/ABC?Q=X HTTP/1.1"
+            .parse::<RequestLine>()
+            .unwrap();
+
+        assert_eq!(request_line.uri.get_path(), "/Users/ABC");
+        assert_eq!(request_line.uri.query(), Some("Q=X"));
+    }
+
+    #[test]
+    fn methods_are_case_sensitive() {
+        for (method, expected) in [
+            ("GET", RequestType::Get),
+            ("HEAD", RequestType::Head),
+            ("POST", RequestType::Post),
+            ("PATCH", RequestType::Patch),
+        ] {
+            assert_eq!(method.parse::<RequestType>().unwrap(), expected);
+        }
+
+        // Lowercase spellings are extension methods of their own, see RFC 9110 section 9.1
+        for method in ["get", "Get", "gEt", "post", "Head"] {
+            let request_type = method.parse::<RequestType>().unwrap();
+
+            assert_eq!(request_type, RequestType::Extension(method.to_owned()));
+            assert_eq!(request_type.to_string(), method);
+        }
+    }
+
+    #[test]
+    fn lowercase_get_request_line_is_not_get() {
+        let request_line = "get /Users/ABC HTTP/1.1".parse::<RequestLine>().unwrap();
+
+        assert_ne!(request_line.request_type, RequestType::Get);
+        assert!(!request_line.request_type.is_safe());
+    }
+
+    #[test]
+    fn removes_dot_and_empty_segments() {
+        for (target, path) in [
+            ("/a/../b", "/b"),
+            ("/a/./b/", "/a/b"),
+            ("/a//b", "/a/b"),
+            ("/..", "/"),
+            ("/../../a", "/a"),
+            ("/a/%2e%2E/b", "/b"),
+            ("/a/.%2e/../..", "/"),
+        ] {
+            assert_eq!(target.parse::<Path>().unwrap().get_path(), path, "{target}");
+        }
+    }
+
+    #[test]
+    fn decodes_percent_encoded_segments() {
+        let path = "/caf%C3%A9/a%20b?x=%41".parse::<Path>().unwrap();
+        assert_eq!(path.segments(), ["caf\u{e9}", "a b"]);
+        assert_eq!(path.get_path(), "/caf\u{e9}/a b");
+        // The query is only decoded into its parameters
+        assert_eq!(path.query(), Some("x=%41"));
+        assert_eq!(path.query_param("x"), Some("A"));
+
+        // Invalid escapes are kept as they are, invalid UTF-8 is replaced
+        let path = "/%zz/100%/%ff".parse::<Path>().unwrap();
+        assert_eq!(path.segments(), ["%zz", "100%", "\u{fffd}"]);
+    }
+
+    #[test]
+    fn keeps_encoded_slashes_within_their_segment() {
+        let path = "/files/a%2Fb/c".parse::<Path>().unwrap();
+
+        assert_eq!(path.segments(), ["files", "a/b", "c"]);
+        assert_eq!(path.get_path(), "/files/a%2Fb/c");
+        assert_ne!(
+            path.get_path(),
+            "/files/a/b/c".parse::<Path>().unwrap().get_path()
+        );
+    }
+
+    #[test]
+    fn parses_absolute_form_targets() {
+        let path = "http://Example.com:8080/a/../b?q=1"
+            .parse::<Path>()
+            .unwrap();
+
+        assert_eq!(path.form(), TargetForm::Absolute);
+        assert_eq!(path.authority(), Some("example.com:8080"));
+        assert_eq!(path.get_path(), "/b");
+        assert_eq!(path.query_param("q"), Some("1"));
+
+        let path = "http://example.com".parse::<Path>().unwrap();
+        assert_eq!(path.authority(), Some("example.com"));
+        assert_eq!(path.get_path(), "/");
+
+        assert!("http://exa mple.com/".parse::<Path>().is_err());
+    }
+
+    #[test]
+    fn asterisk_form_is_only_allowed_for_options() {
+        let path = "*".parse::<Path>().unwrap();
+        assert_eq!(path.form(), TargetForm::Asterisk);
+        assert_eq!(path.get_path(), "*");
+        assert!(path.segments().is_empty());
+
+        assert!("OPTIONS * HTTP/1.1".parse::<RequestLine>().is_ok());
+        assert!("GET * HTTP/1.1".parse::<RequestLine>().is_err());
+        assert!("".parse::<Path>().is_err());
+    }
+
+    #[test]
+    fn http_version_is_case_sensitive() {
+        assert!("HTTP/1.1".parse::<HttpVersion>().is_ok());
+        assert!("http/1.1".parse::<HttpVersion>().is_err());
+        assert!("Http/1.1".parse::<HttpVersion>().is_err());
+    }
+}
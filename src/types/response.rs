@@ -1,17 +1,23 @@
-use super::{body, header, response_line};
+use tokio::sync::mpsc;
+
+use super::{
+    body::{Body, BodyType},
+    header::{ContentLength, ContentType},
+    request_line, response_header, response_line, status,
+};
 
 #[derive(Debug)]
 pub struct ResponseMessage {
     pub response_line: response_line::ResponseLine,
-    pub header: header::Header,
-    pub body: body::Body,
+    pub header: response_header::ResponseHeader,
+    pub body: Body,
 }
 
 impl ResponseMessage {
     pub const fn new(
         response_line: response_line::ResponseLine,
-        header: header::Header,
-        body: body::Body,
+        header: response_header::ResponseHeader,
+        body: Body,
     ) -> Self {
         Self {
             response_line,
@@ -19,6 +25,34 @@ impl ResponseMessage {
             body,
         }
     }
+
+    /// Starts a `200 OK` response, see [`ResponseBuilder`]
+    pub fn builder() -> ResponseBuilder {
+        ResponseBuilder::default()
+    }
+
+    /// Informational response sent ahead of the final one, such as 103 Early Hints, see RFC 9110
+    /// section 15.2
+    ///
+    /// # Panics
+    ///
+    /// Panics if the status is not a 1xx one or is 101, which ends the exchange instead
+    pub fn interim(status: status::Status) -> Self {
+        assert!(
+            status.is_interim(),
+            "Interim responses need a 1xx status other than 101, got {}",
+            status.status_code()
+        );
+
+        Self::new(
+            response_line::ResponseLine::new(
+                request_line::HttpVersion::new(request_line::HttpVersionEnum::V1_1),
+                status,
+            ),
+            response_header::ResponseHeader::default(),
+            Body::default(),
+        )
+    }
 }
 
 impl std::fmt::Display for ResponseMessage {
@@ -30,3 +64,244 @@ impl std::fmt::Display for ResponseMessage {
         )
     }
 }
+
+/// Hands interim responses from a handler to the connection, which writes them while the final
+/// response is still being generated
+#[derive(Debug, Clone, Default)]
+pub struct InterimSender(Option<mpsc::UnboundedSender<ResponseMessage>>);
+
+impl InterimSender {
+    pub fn channel() -> (Self, mpsc::UnboundedReceiver<ResponseMessage>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        (Self(Some(sender)), receiver)
+    }
+
+    /// Returns false if the response can't be delivered, such as to HTTP/1.0 clients or once the
+    /// final response was sent, or if it isn't an interim response at all
+    pub fn send(&self, response: ResponseMessage) -> bool {
+        if !response.response_line.status.is_interim() {
+            return false;
+        }
+
+        self.0
+            .as_ref()
+            .is_some_and(|sender| sender.send(response).is_ok())
+    }
+}
+
+/// Builds a response whose Content-Type and Content-Length are derived from the body
+///
+/// A Content-Type set explicitly takes precedence, the length is always the one of the body so
+/// the two can't disagree.
+#[derive(Debug)]
+pub struct ResponseBuilder {
+    status: status::Status,
+    header: response_header::ResponseHeader,
+}
+
+impl Default for ResponseBuilder {
+    fn default() -> Self {
+        Self {
+            status: status::Status::OK,
+            header: response_header::ResponseHeader::default(),
+        }
+    }
+}
+
+impl ResponseBuilder {
+    #[must_use]
+    pub fn status(mut self, status: status::Status) -> Self {
+        self.status = status;
+        self
+    }
+
+    #[must_use]
+    pub fn content_type(mut self, content_type: ContentType) -> Self {
+        self.header.content_type = Some(content_type);
+        self
+    }
+
+    #[must_use]
+    pub fn location(mut self, location: response_header::Location) -> Self {
+        self.header.location = Some(location);
+        self
+    }
+
+    #[must_use]
+    pub fn cache_control(mut self, cache_control: response_header::CacheControl) -> Self {
+        self.header.cache_control = Some(cache_control);
+        self
+    }
+
+    #[must_use]
+    pub fn etag(mut self, etag: response_header::ETag) -> Self {
+        self.header.etag = Some(etag);
+        self
+    }
+
+    #[must_use]
+    pub const fn last_modified(mut self, last_modified: response_header::LastModified) -> Self {
+        self.header.last_modified = Some(last_modified);
+        self
+    }
+
+    #[must_use]
+    pub const fn expires(mut self, expires: response_header::Expires) -> Self {
+        self.header.expires = Some(expires);
+        self
+    }
+
+    /// Appends a header field without a dedicated setter
+    #[must_use]
+    pub fn header(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.header.other_headers.append(name, value);
+        self
+    }
+
+    pub fn body(mut self, body: Body) -> ResponseMessage {
+        let body_type = body.get_type();
+
+        if self.header.content_type.is_none() {
+            self.header.content_type = default_content_type(body_type);
+        }
+
+        // Streams of unknown length are framed by the connection instead
+        self.header.content_length = match body_type {
+            _ if !self.status.allows_content() => None,
+            BodyType::Stream(stream) => stream.length().map(ContentLength::new),
+            _ => Some(ContentLength::new(body_type.as_bytes().len() as u64)),
+        };
+
+        ResponseMessage::new(
+            response_line::ResponseLine::new(
+                request_line::HttpVersion::new(request_line::HttpVersionEnum::V1_1),
+                self.status,
+            ),
+            self.header,
+            body,
+        )
+    }
+
+    pub fn empty(self) -> ResponseMessage {
+        self.body(Body::new(BodyType::Binary(Vec::new())))
+    }
+}
+
+const fn default_content_type(body_type: &BodyType) -> Option<ContentType> {
+    match body_type {
+        BodyType::TextPlain(_) => Some(ContentType::TEXT_PLAIN),
+        BodyType::TextHtml(_) => Some(ContentType::TEXT_HTML),
+        BodyType::ApplicationJson(_) => Some(ContentType::APPLICATION_JSON),
+        BodyType::ApplicationFormUrlencoded(_) => Some(ContentType::APPLICATION_FORM_URLENCODED),
+        BodyType::Binary(data) if data.is_empty() => None,
+        BodyType::Binary(_) => Some(ContentType::APPLICATION_OCTET_STREAM),
+        BodyType::Stream(_) => None,
+    }
+}
+
+/// Converts a handler's return value into the response sent to the client
+pub trait IntoResponse {
+    fn into_response(self) -> ResponseMessage;
+}
+
+impl IntoResponse for ResponseMessage {
+    fn into_response(self) -> ResponseMessage {
+        self
+    }
+}
+
+impl IntoResponse for Body {
+    fn into_response(self) -> ResponseMessage {
+        ResponseMessage::builder().body(self)
+    }
+}
+
+// An empty response with the given status
+impl IntoResponse for status::Status {
+    fn into_response(self) -> ResponseMessage {
+        ResponseMessage::builder().status(self).empty()
+    }
+}
+
+impl IntoResponse for String {
+    fn into_response(self) -> ResponseMessage {
+        Body::new(BodyType::TextPlain(self)).into_response()
+    }
+}
+
+impl IntoResponse for &str {
+    fn into_response(self) -> ResponseMessage {
+        self.to_owned().into_response()
+    }
+}
+
+impl IntoResponse for serde_json::Value {
+    fn into_response(self) -> ResponseMessage {
+        Body::new(BodyType::ApplicationJson(self)).into_response()
+    }
+}
+
+/// Sends the text as `text/html`
+#[derive(Debug, Clone)]
+pub struct Html<T>(pub T);
+
+impl<T: Into<String>> IntoResponse for Html<T> {
+    fn into_response(self) -> ResponseMessage {
+        Body::new(BodyType::TextHtml(self.0.into())).into_response()
+    }
+}
+
+impl<T: IntoResponse> IntoResponse for (status::Status, T) {
+    fn into_response(self) -> ResponseMessage {
+        let (status, value) = self;
+        let mut response = value.into_response();
+        response.response_line.status = status;
+        response
+    }
+}
+
+impl<T: IntoResponse, E: IntoResponse> IntoResponse for Result<T, E> {
+    fn into_response(self) -> ResponseMessage {
+        match self {
+            Ok(value) => value.into_response(),
+            Err(err) => err.into_response(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interim_sender_only_sends_interim_responses() {
+        let (interim, mut receiver) = InterimSender::channel();
+
+        let mut final_response = ResponseMessage::interim(status::Status::CONTINUE);
+        final_response.response_line.status = status::Status::OK;
+        assert!(!interim.send(final_response));
+
+        let mut switching_protocols = ResponseMessage::interim(status::Status::CONTINUE);
+        switching_protocols.response_line.status = status::Status::SWITCHING_PROTOCOLS;
+        assert!(!interim.send(switching_protocols));
+
+        let early_hints = status::Status::from_code(103).unwrap();
+        assert!(interim.send(ResponseMessage::interim(early_hints.clone())));
+        assert_eq!(
+            receiver.try_recv().unwrap().response_line.status,
+            early_hints
+        );
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn interim_senders_without_a_connection_drop_responses() {
+        assert!(!InterimSender::default().send(ResponseMessage::interim(status::Status::CONTINUE)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Interim responses need a 1xx status other than 101")]
+    fn interim_refuses_switching_protocols() {
+        let _ = ResponseMessage::interim(status::Status::SWITCHING_PROTOCOLS);
+    }
+}
@@ -0,0 +1,181 @@
+use std::{str::FromStr, time::SystemTime};
+
+use super::{
+    header::{ContentLength, ContentType, ParseError, TransferEncoding},
+    header_map::{HeaderMap, TypedHeader},
+};
+
+const DATE_HEADER_NAME: &str = "date";
+const SERVER_HEADER_NAME: &str = "server";
+const LOCATION_HEADER_NAME: &str = "location";
+const CACHE_CONTROL_HEADER_NAME: &str = "cache-control";
+const ETAG_HEADER_NAME: &str = "etag";
+const LAST_MODIFIED_HEADER_NAME: &str = "last-modified";
+const EXPIRES_HEADER_NAME: &str = "expires";
+
+macro_rules! text_header {
+    ($type:ident, $name:expr) => {
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        pub struct $type(String);
+
+        impl $type {
+            pub fn new(value: impl Into<String>) -> Self {
+                Self(value.into())
+            }
+        }
+
+        impl FromStr for $type {
+            type Err = ParseError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Ok(Self(s.trim().to_owned()))
+            }
+        }
+
+        impl TypedHeader for $type {
+            const NAME: &'static str = $name;
+
+            fn encode(&self) -> String {
+                self.0.clone()
+            }
+        }
+
+        impl std::fmt::Display for $type {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{}", self.encode())
+            }
+        }
+    };
+}
+
+macro_rules! date_header {
+    ($type:ident, $name:expr) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub struct $type(SystemTime);
+
+        impl $type {
+            pub const fn new(time: SystemTime) -> Self {
+                Self(time)
+            }
+        }
+
+        impl FromStr for $type {
+            type Err = ParseError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Ok(Self(httpdate::parse_http_date(s.trim())?))
+            }
+        }
+
+        impl TypedHeader for $type {
+            const NAME: &'static str = $name;
+
+            fn encode(&self) -> String {
+                httpdate::fmt_http_date(self.0)
+            }
+        }
+
+        impl std::fmt::Display for $type {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{}", self.encode())
+            }
+        }
+    };
+}
+
+text_header!(Server, SERVER_HEADER_NAME);
+text_header!(Location, LOCATION_HEADER_NAME);
+text_header!(CacheControl, CACHE_CONTROL_HEADER_NAME);
+text_header!(ETag, ETAG_HEADER_NAME);
+
+date_header!(Date, DATE_HEADER_NAME);
+date_header!(LastModified, LAST_MODIFIED_HEADER_NAME);
+date_header!(Expires, EXPIRES_HEADER_NAME);
+
+impl Date {
+    pub fn now() -> Self {
+        Self(SystemTime::now())
+    }
+}
+
+impl Default for Server {
+    fn default() -> Self {
+        Self(format!(
+            "{}/{}",
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION")
+        ))
+    }
+}
+
+/// Header fields of a response, built by handlers independently from the request ones
+#[derive(Debug, Default)]
+pub struct ResponseHeader {
+    pub date: Option<Date>,
+    pub server: Option<Server>,
+    pub location: Option<Location>,
+    pub cache_control: Option<CacheControl>,
+    pub etag: Option<ETag>,
+    pub last_modified: Option<LastModified>,
+    pub expires: Option<Expires>,
+    pub content_type: Option<ContentType>,
+    pub content_length: Option<ContentLength>,
+    pub transfer_encoding: Option<TransferEncoding>,
+    pub other_headers: HeaderMap,
+}
+
+impl ResponseHeader {
+    pub fn new(content_type: ContentType, content_length: ContentLength) -> Self {
+        Self {
+            content_type: Some(content_type),
+            content_length: Some(content_length),
+            ..Self::default()
+        }
+    }
+}
+
+impl From<&ResponseHeader> for HeaderMap {
+    fn from(header: &ResponseHeader) -> Self {
+        let mut header_map = Self::new();
+
+        macro_rules! append_optional {
+            ($($field:ident),+) => {
+                $(
+                    if let Some($field) = &header.$field {
+                        header_map.append_typed($field);
+                    }
+                )+
+            };
+        }
+
+        append_optional!(
+            date,
+            server,
+            location,
+            cache_control,
+            etag,
+            last_modified,
+            expires,
+            content_type
+        );
+
+        // Content-Length must not be sent together with Transfer-Encoding
+        match (&header.transfer_encoding, &header.content_length) {
+            (Some(transfer_encoding), _) => header_map.append_typed(transfer_encoding),
+            (None, Some(content_length)) => header_map.append_typed(content_length),
+            (None, None) => {}
+        }
+
+        for (name, value) in header.other_headers.iter() {
+            header_map.append(name, value);
+        }
+
+        header_map
+    }
+}
+
+impl std::fmt::Display for ResponseHeader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", HeaderMap::from(self))
+    }
+}
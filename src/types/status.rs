@@ -1,55 +1,320 @@
-use std::str::FromStr;
+use std::{borrow::Cow, str::FromStr};
 
 use thiserror::Error;
 
+const MIN_STATUS_CODE: u16 = 100;
+const MAX_STATUS_CODE: u16 = 599;
+
 #[derive(Error, Debug)]
 pub enum ParseError {
-    #[error("Unknown Status Code: {0}")]
-    UnknownStatusCode(String),
+    #[error("Invalid Status Code: {0}")]
+    InvalidStatusCode(String),
+
+    #[error("Status Code is out of range: {0}")]
+    StatusCodeOutOfRange(u16),
+
+    #[error("Invalid reason phrase: {0:?}")]
+    InvalidReasonPhrase(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Status {
+    code: u16,
+    reason: Option<Cow<'static, str>>,
+}
+
+macro_rules! status_codes {
+    ($(($name:ident, $code:literal, $reason:literal),)+) => {
+        impl Status {
+            $(
+                pub const $name: Self = Self {
+                    code: $code,
+                    reason: None,
+                };
+            )+
+        }
+
+        // Registered codes and reason phrases from the IANA HTTP Status Code Registry
+        const fn canonical_reason(code: u16) -> Option<&'static str> {
+            match code {
+                $($code => Some($reason),)+
+                _ => None,
+            }
+        }
+    };
 }
 
-// TODO: Maybe it's better to make it a wrapper around enum
-#[derive(PartialEq, Eq, Debug)]
-pub struct Status(u64);
+status_codes! {
+    (CONTINUE, 100, "Continue"),
+    (SWITCHING_PROTOCOLS, 101, "Switching Protocols"),
+    (PROCESSING, 102, "Processing"),
+    (EARLY_HINTS, 103, "Early Hints"),
+    (OK, 200, "OK"),
+    (CREATED, 201, "Created"),
+    (ACCEPTED, 202, "Accepted"),
+    (NON_AUTHORITATIVE_INFORMATION, 203, "Non-Authoritative Information"),
+    (NO_CONTENT, 204, "No Content"),
+    (RESET_CONTENT, 205, "Reset Content"),
+    (PARTIAL_CONTENT, 206, "Partial Content"),
+    (MULTI_STATUS, 207, "Multi-Status"),
+    (ALREADY_REPORTED, 208, "Already Reported"),
+    (IM_USED, 226, "IM Used"),
+    (MULTIPLE_CHOICES, 300, "Multiple Choices"),
+    (MOVED_PERMANENTLY, 301, "Moved Permanently"),
+    (FOUND, 302, "Found"),
+    (SEE_OTHER, 303, "See Other"),
+    (NOT_MODIFIED, 304, "Not Modified"),
+    (USE_PROXY, 305, "Use Proxy"),
+    (TEMPORARY_REDIRECT, 307, "Temporary Redirect"),
+    (PERMANENT_REDIRECT, 308, "Permanent Redirect"),
+    (BAD_REQUEST, 400, "Bad Request"),
+    (UNAUTHORIZED, 401, "Unauthorized"),
+    (PAYMENT_REQUIRED, 402, "Payment Required"),
+    (FORBIDDEN, 403, "Forbidden"),
+    (NOT_FOUND, 404, "Not Found"),
+    (METHOD_NOT_ALLOWED, 405, "Method Not Allowed"),
+    (NOT_ACCEPTABLE, 406, "Not Acceptable"),
+    (PROXY_AUTHENTICATION_REQUIRED, 407, "Proxy Authentication Required"),
+    (REQUEST_TIMEOUT, 408, "Request Timeout"),
+    (CONFLICT, 409, "Conflict"),
+    (GONE, 410, "Gone"),
+    (LENGTH_REQUIRED, 411, "Length Required"),
+    (PRECONDITION_FAILED, 412, "Precondition Failed"),
+    (CONTENT_TOO_LARGE, 413, "Content Too Large"),
+    (URI_TOO_LONG, 414, "URI Too Long"),
+    (UNSUPPORTED_MEDIA_TYPE, 415, "Unsupported Media Type"),
+    (RANGE_NOT_SATISFIABLE, 416, "Range Not Satisfiable"),
+    (EXPECTATION_FAILED, 417, "Expectation Failed"),
+    (MISDIRECTED_REQUEST, 421, "Misdirected Request"),
+    (UNPROCESSABLE_CONTENT, 422, "Unprocessable Content"),
+    (LOCKED, 423, "Locked"),
+    (FAILED_DEPENDENCY, 424, "Failed Dependency"),
+    (TOO_EARLY, 425, "Too Early"),
+    (UPGRADE_REQUIRED, 426, "Upgrade Required"),
+    (PRECONDITION_REQUIRED, 428, "Precondition Required"),
+    (TOO_MANY_REQUESTS, 429, "Too Many Requests"),
+    (REQUEST_HEADER_FIELDS_TOO_LARGE, 431, "Request Header Fields Too Large"),
+    (UNAVAILABLE_FOR_LEGAL_REASONS, 451, "Unavailable For Legal Reasons"),
+    (INTERNAL_SERVER_ERROR, 500, "Internal Server Error"),
+    (NOT_IMPLEMENTED, 501, "Not Implemented"),
+    (BAD_GATEWAY, 502, "Bad Gateway"),
+    (SERVICE_UNAVAILABLE, 503, "Service Unavailable"),
+    (GATEWAY_TIMEOUT, 504, "Gateway Timeout"),
+    (HTTP_VERSION_NOT_SUPPORTED, 505, "HTTP Version Not Supported"),
+    (VARIANT_ALSO_NEGOTIATES, 506, "Variant Also Negotiates"),
+    (INSUFFICIENT_STORAGE, 507, "Insufficient Storage"),
+    (LOOP_DETECTED, 508, "Loop Detected"),
+    (NOT_EXTENDED, 510, "Not Extended"),
+    (NETWORK_AUTHENTICATION_REQUIRED, 511, "Network Authentication Required"),
+}
 
 impl Status {
-    pub const OK_STATUS_NAME: &str = "OK";
-    pub const NOT_FOUND_STATUS_NAME: &str = "Not found";
-    pub const INTERNAL_SERVER_ERROR_STATUS_NAME: &str = "Internal Server Error";
+    /// # Errors
+    ///
+    /// Returns an error if the code is outside of 100 to 599
+    pub const fn from_code(code: u16) -> Result<Self, ParseError> {
+        if code < MIN_STATUS_CODE || code > MAX_STATUS_CODE {
+            return Err(ParseError::StatusCodeOutOfRange(code));
+        }
+
+        Ok(Self { code, reason: None })
+    }
+
+    /// # Errors
+    ///
+    /// Returns an error if the code is outside of 100 to 599 or the reason phrase contains
+    /// characters other than tabs, spaces, visible characters and obs-text, see RFC 9112 section 4
+    pub fn custom(code: u16, reason: impl Into<Cow<'static, str>>) -> Result<Self, ParseError> {
+        let reason = reason.into();
+
+        // Control characters such as CR and LF would let the phrase end the status line
+        if reason
+            .bytes()
+            .any(|byte| byte != b'\t' && (byte < b' ' || byte == 0x7f))
+        {
+            return Err(ParseError::InvalidReasonPhrase(reason.into_owned()));
+        }
 
-    pub const OK: Self = Self(200);
-    pub const NOT_FOUND: Self = Self(404);
-    pub const INTERNAL_SERVER_ERROR: Self = Self(500);
+        Ok(Self {
+            reason: Some(reason),
+            ..Self::from_code(code)?
+        })
+    }
+
+    pub const fn status_code(&self) -> u16 {
+        self.code
+    }
+
+    pub const fn canonical_reason(&self) -> Option<&'static str> {
+        canonical_reason(self.code)
+    }
+
+    /// Caller-supplied reason phrase if there is one, otherwise the registered one
+    pub fn reason_phrase(&self) -> &str {
+        self.reason
+            .as_deref()
+            .or_else(|| self.canonical_reason())
+            .unwrap_or_default()
+    }
+
+    pub const fn is_informational(&self) -> bool {
+        self.code >= 100 && self.code < 200
+    }
 
-    pub const fn status_code(&self) -> u64 {
-        self.0
+    /// 1xx responses that precede the final one, 101 ends the exchange instead, see RFC 9110
+    /// section 15.2
+    pub const fn is_interim(&self) -> bool {
+        self.is_informational() && self.code != 101
+    }
+
+    pub const fn is_success(&self) -> bool {
+        self.code >= 200 && self.code < 300
+    }
+
+    pub const fn is_redirection(&self) -> bool {
+        self.code >= 300 && self.code < 400
+    }
+
+    pub const fn is_client_error(&self) -> bool {
+        self.code >= 400 && self.code < 500
+    }
+
+    pub const fn is_server_error(&self) -> bool {
+        self.code >= 500 && self.code < 600
+    }
+
+    /// 1xx, 204 and 304 responses never carry content, see RFC 9110 section 6.4.1
+    pub const fn allows_content(&self) -> bool {
+        !self.is_informational() && self.code != 204 && self.code != 304
     }
 }
 
+impl PartialEq for Status {
+    fn eq(&self, other: &Self) -> bool {
+        self.code == other.code
+    }
+}
+
+impl Eq for Status {}
+
 impl FromStr for Status {
     type Err = ParseError;
 
+    // Accepts either a bare code or a code followed by a reason phrase, as in a status line
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            Self::OK_STATUS_NAME => Ok(Self::OK),
-            Self::NOT_FOUND_STATUS_NAME => Ok(Self::NOT_FOUND),
-            Self::INTERNAL_SERVER_ERROR_STATUS_NAME => Ok(Self::INTERNAL_SERVER_ERROR),
-            unknown => Err(ParseError::UnknownStatusCode(unknown.to_owned())),
+        let trimmed = s.trim();
+        let (code, reason) = match trimmed.split_once(' ') {
+            Some((code, reason)) => (code, Some(reason.trim())),
+            None => (trimmed, None),
+        };
+
+        if code.len() != 3 || !code.bytes().all(|byte| byte.is_ascii_digit()) {
+            return Err(ParseError::InvalidStatusCode(s.to_owned()));
+        }
+
+        let code = code
+            .parse()
+            .map_err(|_| ParseError::InvalidStatusCode(s.to_owned()))?;
+
+        match reason {
+            Some(reason) if canonical_reason(code) != Some(reason) => {
+                Self::custom(code, reason.to_owned())
+            }
+            _ => Self::from_code(code),
         }
     }
 }
 
 impl std::fmt::Display for Status {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match *self {
-                Self::OK => Self::OK_STATUS_NAME,
-                Self::NOT_FOUND => Self::NOT_FOUND_STATUS_NAME,
-                Self::INTERNAL_SERVER_ERROR => Self::INTERNAL_SERVER_ERROR_STATUS_NAME,
-                _ => "<status code is unknown>",
-            }
-        )
+        write!(f, "{}", self.reason_phrase())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn custom_rejects_control_characters_in_reason() {
+        for reason in ["Bad\r\nSet-Cookie: a=b", "Line\nFeed", "Nul\0", "Del\x7f"] {
+            assert!(matches!(
+                Status::custom(299, reason),
+                Err(ParseError::InvalidReasonPhrase(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn custom_accepts_tabs_spaces_and_obs_text() {
+        let status = Status::custom(299, "Mostly\tFine Ünïcode").unwrap();
+
+        assert_eq!(status.status_code(), 299);
+        assert_eq!(status.reason_phrase(), "Mostly\tFine Ünïcode");
+    }
+
+    #[test]
+    fn custom_rejects_codes_out_of_range() {
+        assert!(matches!(
+            Status::custom(600, "Nope"),
+            Err(ParseError::StatusCodeOutOfRange(600))
+        ));
+    }
+
+    #[test]
+    fn from_code_round_trips_through_from_str() {
+        for code in [
+            MIN_STATUS_CODE,
+            200,
+            204,
+            304,
+            404,
+            418,
+            499,
+            MAX_STATUS_CODE,
+        ] {
+            let status = Status::from_code(code).unwrap();
+            let parsed = format!("{} {status}", status.status_code())
+                .parse::<Status>()
+                .unwrap();
+
+            assert_eq!(parsed, status);
+            assert_eq!(parsed.reason_phrase(), status.reason_phrase());
+            assert_eq!(code.to_string().parse::<Status>().unwrap(), status);
+        }
+    }
+
+    #[test]
+    fn from_code_rejects_codes_out_of_range() {
+        assert!(Status::from_code(99).is_err());
+        assert!(Status::from_code(600).is_err());
+    }
+
+    #[test]
+    fn from_str_keeps_custom_reason() {
+        let status = "404 Nothing Here".parse::<Status>().unwrap();
+
+        assert_eq!(status, Status::NOT_FOUND);
+        assert_eq!(status.reason_phrase(), "Nothing Here");
+        assert_eq!(status.to_string(), "Nothing Here");
+    }
+
+    #[test]
+    fn only_1xx_other_than_101_are_interim() {
+        assert!(Status::CONTINUE.is_interim());
+        assert!(Status::from_code(103).unwrap().is_interim());
+        assert!(!Status::SWITCHING_PROTOCOLS.is_interim());
+        assert!(!Status::OK.is_interim());
+        assert!(!Status::NO_CONTENT.is_interim());
+    }
+
+    #[test]
+    fn from_str_rejects_invalid_input() {
+        for input in ["", "20", "2000", "abc", "2x0 OK", "700", "200 Bad\rReason"] {
+            assert!(
+                input.parse::<Status>().is_err(),
+                "{input:?} should not parse"
+            );
+        }
     }
 }
@@ -0,0 +1,314 @@
+use std::net::SocketAddr;
+
+use http::{
+    types::body::{BodyStream, BodyType},
+    Body, RequestMessage, ResponseMessage, Router, Server,
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+    sync::oneshot,
+    task::JoinHandle,
+};
+
+const PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+const DATA: u8 = 0x0;
+const HEADERS: u8 = 0x1;
+const RST_STREAM: u8 = 0x3;
+const SETTINGS: u8 = 0x4;
+const GOAWAY: u8 = 0x7;
+
+const END_STREAM: u8 = 0x1;
+const END_HEADERS: u8 = 0x4;
+
+const PROTOCOL_ERROR: u32 = 0x1;
+const INTERNAL_ERROR: u32 = 0x2;
+
+// `:status: 200` is entry 8 of the static table, which the server always indexes
+const STATUS_200: u8 = 0x88;
+
+struct TestServer {
+    address: SocketAddr,
+    shutdown: oneshot::Sender<()>,
+    task: JoinHandle<()>,
+}
+
+impl TestServer {
+    async fn start() -> Self {
+        let router = Router::new()
+            .get("/hello", |request_message: RequestMessage| async move {
+                format!("hello over {}", request_message.request_line.http_version)
+            })
+            .get("/long", |_| async { stream_response(b"hello, world", 5) })
+            .get("/short", |_| async { stream_response(b"hello", 10) })
+            .post("/echo", |request_message: RequestMessage| async move {
+                String::from_utf8_lossy(&request_message.body.bytes()).into_owned()
+            });
+
+        let server = Server::builder()
+            .bind("127.0.0.1:0")
+            .router(router)
+            .build()
+            .await
+            .unwrap();
+        let address = server.local_addrs()[0];
+
+        let (shutdown, signal) = oneshot::channel();
+        let task = tokio::spawn(async move {
+            server
+                .run_until(async {
+                    signal.await.ok();
+                })
+                .await
+                .unwrap();
+        });
+
+        Self {
+            address,
+            shutdown,
+            task,
+        }
+    }
+
+    async fn stop(self) {
+        self.shutdown.send(()).unwrap();
+        self.task.await.unwrap();
+    }
+}
+
+// Body stream whose announced length disagrees with what it yields
+fn stream_response(data: &'static [u8], length: u64) -> ResponseMessage {
+    let body_stream = BodyStream::from_reader(data).with_length(length);
+    ResponseMessage::builder().body(Body::new(BodyType::Stream(body_stream)))
+}
+
+fn frame(kind: u8, flags: u8, stream_id: u32, payload: &[u8]) -> Vec<u8> {
+    let length = u32::try_from(payload.len()).unwrap().to_be_bytes();
+
+    let mut frame = length[1..].to_vec();
+    frame.push(kind);
+    frame.push(flags);
+    frame.extend_from_slice(&stream_id.to_be_bytes());
+    frame.extend_from_slice(payload);
+    frame
+}
+
+async fn read_frame<R: AsyncReadExt + Unpin>(reader: &mut R) -> (u8, u8, u32, Vec<u8>) {
+    let mut header = [0u8; 9];
+    reader.read_exact(&mut header).await.unwrap();
+
+    let length = u32::from_be_bytes([0, header[0], header[1], header[2]]);
+    let stream_id = u32::from_be_bytes([header[5], header[6], header[7], header[8]]);
+
+    let mut payload = vec![0u8; length as usize];
+    reader.read_exact(&mut payload).await.unwrap();
+
+    (header[3], header[4], stream_id, payload)
+}
+
+// `GET` with literal fields that are never indexed, so no dynamic table is involved
+fn get_block(path: &str) -> Vec<u8> {
+    let mut block = vec![0x82, 0x86];
+    for (index, value) in [(0x04, path), (0x01, "localhost")] {
+        block.push(index);
+        block.push(u8::try_from(value.len()).unwrap());
+        block.extend_from_slice(value.as_bytes());
+    }
+    block
+}
+
+// `POST /echo` declaring a content-length, as a literal field with a new name
+fn post_echo_block(content_length: usize) -> Vec<u8> {
+    let mut block = vec![0x83, 0x86];
+    for (index, value) in [(0x04, "/echo"), (0x01, "localhost")] {
+        block.push(index);
+        block.push(u8::try_from(value.len()).unwrap());
+        block.extend_from_slice(value.as_bytes());
+    }
+
+    let content_length = content_length.to_string();
+    block.push(0x00);
+    block.push(14);
+    block.extend_from_slice(b"content-length");
+    block.push(u8::try_from(content_length.len()).unwrap());
+    block.extend_from_slice(content_length.as_bytes());
+    block
+}
+
+/// Reads frames until stream 1 ends, returns the header block and the body of the response
+async fn read_response<R: AsyncReadExt + Unpin>(reader: &mut R) -> (Vec<u8>, Vec<u8>) {
+    let (block, body, reset) = read_stream(reader, 1).await;
+    assert_eq!(reset, None, "stream was reset");
+    (block, body)
+}
+
+/// Reads frames until the stream ends or is reset, returns the header block, the body and the
+/// error code of a reset
+async fn read_stream<R: AsyncReadExt + Unpin>(
+    reader: &mut R,
+    expected_stream_id: u32,
+) -> (Vec<u8>, Vec<u8>, Option<u32>) {
+    let mut block = Vec::new();
+    let mut body = Vec::new();
+
+    loop {
+        let (kind, flags, stream_id, payload) = read_frame(reader).await;
+        assert_ne!(kind, GOAWAY, "connection closed early: {payload:?}");
+
+        if stream_id != expected_stream_id {
+            continue;
+        }
+
+        match kind {
+            HEADERS => block.extend_from_slice(&payload),
+            DATA => body.extend_from_slice(&payload),
+            RST_STREAM => {
+                let error_code = u32::from_be_bytes(payload[..4].try_into().unwrap());
+                return (block, body, Some(error_code));
+            }
+            _ => {}
+        }
+
+        if flags & END_STREAM != 0 {
+            return (block, body, None);
+        }
+    }
+}
+
+#[tokio::test]
+async fn serves_prior_knowledge_connections() {
+    let server = TestServer::start().await;
+    let mut stream = TcpStream::connect(server.address).await.unwrap();
+
+    let mut request = PREFACE.to_vec();
+    request.extend(frame(SETTINGS, 0, 0, &[]));
+    request.extend(frame(
+        HEADERS,
+        END_STREAM | END_HEADERS,
+        1,
+        &get_block("/hello"),
+    ));
+    stream.write_all(&request).await.unwrap();
+
+    // The server preface comes first
+    let (kind, _, stream_id, _) = read_frame(&mut stream).await;
+    assert_eq!((kind, stream_id), (SETTINGS, 0));
+
+    let (block, body) = read_response(&mut stream).await;
+    assert_eq!(block.first(), Some(&STATUS_200));
+    assert_eq!(body, b"hello over HTTP/2.0");
+
+    drop(stream);
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn serves_h2c_upgrades() {
+    let server = TestServer::start().await;
+    let mut stream = BufReader::new(TcpStream::connect(server.address).await.unwrap());
+
+    // HTTP2-Settings carries SETTINGS_MAX_CONCURRENT_STREAMS = 100
+    stream
+        .write_all(
+            b"GET /hello HTTP/1.1\r\n\
+              Host: localhost\r\n\
+              Connection: Upgrade, HTTP2-Settings\r\n\
+              Upgrade: h2c\r\n\
+              HTTP2-Settings: AAMAAABk\r\n\
+              \r\n",
+        )
+        .await
+        .unwrap();
+
+    let mut status_line = String::new();
+    stream.read_line(&mut status_line).await.unwrap();
+    assert!(
+        status_line.starts_with("HTTP/1.1 101 "),
+        "unexpected status line: {status_line}"
+    );
+
+    let mut line = String::new();
+    while line != "\r\n" {
+        line.clear();
+        stream.read_line(&mut line).await.unwrap();
+    }
+
+    let mut preface = PREFACE.to_vec();
+    preface.extend(frame(SETTINGS, 0, 0, &[]));
+    stream.write_all(&preface).await.unwrap();
+
+    // The upgrading request is answered on stream 1
+    let (block, body) = read_response(&mut stream).await;
+    assert_eq!(block.first(), Some(&STATUS_200));
+    assert_eq!(body, b"hello over HTTP/2.0");
+
+    drop(stream);
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn keeps_stream_bodies_to_their_length() {
+    let server = TestServer::start().await;
+    let mut stream = TcpStream::connect(server.address).await.unwrap();
+
+    let mut request = PREFACE.to_vec();
+    request.extend(frame(SETTINGS, 0, 0, &[]));
+    request.extend(frame(
+        HEADERS,
+        END_STREAM | END_HEADERS,
+        1,
+        &get_block("/long"),
+    ));
+    stream.write_all(&request).await.unwrap();
+
+    // Bytes past the announced length are dropped
+    let (block, body) = read_response(&mut stream).await;
+    assert_eq!(block.first(), Some(&STATUS_200));
+    assert_eq!(body, b"hello");
+
+    // A body that ends early can't be completed, so its stream is reset
+    let request = frame(HEADERS, END_STREAM | END_HEADERS, 3, &get_block("/short"));
+    stream.write_all(&request).await.unwrap();
+
+    let (_, body, reset) = read_stream(&mut stream, 3).await;
+    assert_eq!(body, b"hello");
+    assert_eq!(reset, Some(INTERNAL_ERROR));
+
+    drop(stream);
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn resets_bodies_that_disagree_with_their_content_length() {
+    let server = TestServer::start().await;
+    let mut stream = TcpStream::connect(server.address).await.unwrap();
+
+    let mut request = PREFACE.to_vec();
+    request.extend(frame(SETTINGS, 0, 0, &[]));
+    stream.write_all(&request).await.unwrap();
+
+    // More data than declared, shorter than declared, and exactly as declared
+    for (stream_id, content_length, data) in [(1, 3, "hello"), (3, 5, "hel"), (5, 5, "hello")] {
+        let mut request = frame(
+            HEADERS,
+            END_HEADERS,
+            stream_id,
+            &post_echo_block(content_length),
+        );
+        request.extend(frame(DATA, END_STREAM, stream_id, data.as_bytes()));
+        stream.write_all(&request).await.unwrap();
+
+        let (block, body, reset) = read_stream(&mut stream, stream_id).await;
+        if stream_id == 5 {
+            assert_eq!(reset, None);
+            assert_eq!(block.first(), Some(&STATUS_200));
+            assert_eq!(body, b"hello");
+        } else {
+            assert_eq!(reset, Some(PROTOCOL_ERROR), "stream {stream_id}");
+        }
+    }
+
+    drop(stream);
+    server.stop().await;
+}